@@ -1,21 +1,421 @@
 use structopt::StructOpt;
-use std::path::PathBuf;
-use say::Schema;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use regex::Regex;
+use say::error::{ErrorCategory, ErrorCode, ValidationError};
+use say::{CompiledSchema, Schema};
+use serde_json::Value;
 
 #[derive(StructOpt, Debug)]
 struct Args {
+    #[structopt(subcommand)]
+    command: Option<Command>,
+    /// The documents to validate, each one against the same schema
     #[structopt(parse(from_os_str))]
-    file: PathBuf,
-    #[structopt(short = "s", long = "schema")]
+    files: Vec<PathBuf>,
+    #[structopt(short = "s", long = "schema", conflicts_with = "convention")]
     schema: Option<String>,
+    /// Pick each file's schema by its name instead, like `{name}.{kind}.json -> {kind}.schema.json`,
+    /// the schema is looked up next to the file
+    #[structopt(long = "convention")]
+    convention: Option<Convention>,
+    /// The media type of the documents, like an HTTP `Content-Type`, picking their schema from `--schema-for`
+    #[structopt(long = "content-type", conflicts_with = "convention")]
+    content_type: Option<String>,
+    /// The schema for one media type, like `application/problem+json=problem.schema.json`,
+    /// `--schema` stays the schema for any other type
+    #[structopt(long = "schema-for", number_of_values = 1, requires = "content-type")]
+    schema_for: Vec<SchemaFor>,
+    /// How to print the results, `text` or `junit` for a JUnit XML testsuite with a testcase per file
+    #[structopt(long = "format", default_value = "text")]
+    format: Format,
+    /// Count the errors of each code instead of listing them, with a few of their paths
+    #[structopt(long = "group-by-code")]
+    group_by_code: bool,
+    /// Print at most this many errors per file, and how many were left out
+    #[structopt(long = "max-errors")]
+    max_errors: Option<usize>,
+    /// Exit with the sum of the kinds of failure found instead of 1: 1 for type errors,
+    /// 2 for constraint errors, 4 for missing fields and 8 for a document that cannot be parsed.
+    /// Anything else, like a missing argument, an unreadable file or a broken schema, exits with 64
+    #[structopt(long = "bitflag-exit")]
+    bitflag_exit: bool,
+}
+
+/// outside every sum of `--bitflag-exit` flags, so it cannot be mistaken for one
+const BITFLAG_USAGE_ERROR: i32 = 64;
+
+#[derive(Debug, Clone, Copy)]
+enum Format {
+    Text,
+    Junit,
+}
+
+impl std::str::FromStr for Format {
+    type Err = String;
+
+    fn from_str(format: &str) -> Result<Self, Self::Err> {
+        match format {
+            "text" => Ok(Format::Text),
+            "junit" => Ok(Format::Junit),
+            _ => Err(format!("unknown format `{}`, expected `text` or `junit`", format)),
+        }
+    }
+}
+
+#[derive(StructOpt, Debug)]
+enum Command {
+    /// Print the fully-resolved schema as JSON
+    Resolve {
+        #[structopt(parse(from_os_str))]
+        schema: PathBuf,
+    },
+    /// Print a Markdown table of every field in the schema
+    Doc {
+        #[structopt(parse(from_os_str))]
+        schema: PathBuf,
+    },
+    /// Validate every golden example file in a directory against the schema
+    CheckExamples {
+        #[structopt(parse(from_os_str))]
+        schema: PathBuf,
+        #[structopt(long = "examples-dir", parse(from_os_str))]
+        examples_dir: PathBuf,
+    },
+    /// Rewrite a schema in place, renaming deprecated fields to their current names
+    Migrate {
+        #[structopt(parse(from_os_str))]
+        schema: PathBuf,
+    },
+}
+
+fn load_schema(path: impl AsRef<Path>) -> Result<Schema, String> {
+    let path = path.as_ref();
+    let content = std::fs::read_to_string(path).map_err(|err| format!("cannot read {}: {}", path.display(), err))?;
+    say::loader::from_str(&content).map_err(|err| format!("invalid schema {}: {}", path.display(), err))
+}
+
+fn compile_schema(path: impl AsRef<Path>) -> Result<CompiledSchema, String> {
+    let schema = load_schema(&path)?;
+    CompiledSchema::compile(schema).map_err(|err| format!("invalid schema {}: {}", path.as_ref().display(), err))
+}
+
+/// print the message and exit, for failures that have nothing to do with the document itself
+fn exit_with_error(message: impl std::fmt::Display, code: i32) -> ! {
+    eprintln!("error: {}", message);
+    std::process::exit(code);
+}
+
+fn check_examples(schema: PathBuf, examples_dir: PathBuf) -> Result<bool, String> {
+    let compiled = compile_schema(schema)?;
+    let entries = std::fs::read_dir(&examples_dir).map_err(|err| format!("cannot read {}: {}", examples_dir.display(), err))?;
+    let mut examples: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_file())
+        .collect();
+    examples.sort();
+
+    let mut all_conform = true;
+    for example in examples {
+        let conforms = match std::fs::read_to_string(&example).map(|content| say::loader::from_str::<Value>(&content)) {
+            Ok(Ok(value)) => compiled.validate(&value),
+            Ok(Err(_)) | Err(_) => false,
+        };
+        if conforms {
+            println!("ok      {}", example.display());
+        } else {
+            println!("broken  {}", example.display());
+            all_conform = false;
+        }
+    }
+    Ok(all_conform)
+}
+
+/// A mapping from data file names to schema file names, with `{placeholder}`s carried over.
+#[derive(Debug)]
+struct Convention {
+    spec: String,
+    file: Regex,
+    schema: String,
+}
+
+impl std::str::FromStr for Convention {
+    type Err = String;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let (file, schema) = spec.split_once("->").ok_or_else(|| format!("convention `{}` has no `->`", spec))?;
+        let (file, schema) = (file.trim(), schema.trim());
+        let placeholder = Regex::new(r"\{(\w+)\}").unwrap();
+        let mut pattern = String::from("^");
+        let mut names = vec![];
+        let mut last = 0;
+        for captures in placeholder.captures_iter(file) {
+            let (whole, name) = (captures.get(0).unwrap(), &captures[1]);
+            pattern.push_str(&regex::escape(&file[last..whole.start()]));
+            pattern.push_str(&format!("(?P<{}>.+?)", name));
+            names.push(name.to_owned());
+            last = whole.end();
+        }
+        pattern.push_str(&regex::escape(&file[last..]));
+        pattern.push('$');
+        if let Some(unknown) = placeholder.captures_iter(schema).find(|captures| !names.contains(&captures[1].to_owned())) {
+            return Err(format!("convention `{}` uses `{}` without matching it in the file name", spec, &unknown[0]));
+        }
+        let file = Regex::new(&pattern).map_err(|err| format!("invalid convention `{}`: {}", spec, err))?;
+        Ok(Convention { spec: spec.to_owned(), file, schema: schema.to_owned() })
+    }
+}
+
+impl Convention {
+    /// the schema next to `file`, `None` when its name does not fit the convention
+    fn schema_for(&self, file: &Path) -> Option<PathBuf> {
+        let name = file.file_name()?.to_str()?;
+        let captures = self.file.captures(name)?;
+        let mut schema = String::new();
+        captures.expand(&self.schema.replace('$', "$$").replace('{', "${"), &mut schema);
+        Some(file.with_file_name(schema))
+    }
+}
+
+/// how the errors of a file are listed
+struct Listing {
+    group_by_code: bool,
+    max_errors: Option<usize>,
+}
+
+/// A `--schema-for` mapping from a media type to a schema path.
+#[derive(Debug)]
+struct SchemaFor {
+    content_type: String,
+    schema: String,
+}
+
+impl std::str::FromStr for SchemaFor {
+    type Err = String;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let (content_type, schema) = spec.split_once('=').ok_or_else(|| format!("expected `<content type>=<schema>`, not `{}`", spec))?;
+        Ok(SchemaFor { content_type: media_type(content_type), schema: schema.trim().to_owned() })
+    }
+}
+
+/// the type without parameters like `charset`, which match regardless of case
+fn media_type(content_type: &str) -> String {
+    content_type.split(';').next().unwrap_or_default().trim().to_ascii_lowercase()
+}
+
+/// the schema mapped to the content type, or the default one
+fn schema_for_content_type(content_type: &str, mappings: &[SchemaFor], default: Option<&str>) -> Result<String, String> {
+    let content_type = media_type(content_type);
+    match mappings.iter().find(|mapping| mapping.content_type == content_type) {
+        Some(mapping) => Ok(mapping.schema.clone()),
+        None => default
+            .map(str::to_owned)
+            .ok_or_else(|| format!("no schema for content type `{}`, map one with `--schema-for {}=<path>`", content_type, content_type)),
+    }
+}
+
+/// what became of one document
+enum Report {
+    Valid,
+    /// not JSON at all, with the parser's message
+    Unparsable(String),
+    Invalid(Vec<ValidationError>),
+}
+
+impl Report {
+    /// `0` for a valid document
+    fn exit_code(&self, bitflag_exit: bool) -> i32 {
+        match self {
+            Report::Valid => 0,
+            Report::Unparsable(_) => if bitflag_exit { 8 } else { 1 },
+            Report::Invalid(_) if !bitflag_exit => 1,
+            Report::Invalid(errors) => errors.iter().fold(0, |flags, error| {
+                flags
+                    | match error.code.category() {
+                        ErrorCategory::Type => 1,
+                        ErrorCategory::Constraint => 2,
+                        ErrorCategory::Missing => 4,
+                    }
+            }),
+        }
+    }
+
+    fn error_count(&self) -> usize {
+        match self {
+            Report::Valid => 0,
+            Report::Unparsable(_) => 1,
+            Report::Invalid(errors) => errors.len(),
+        }
+    }
+
+    /// one line per problem, or per error code when grouping, cut short at `max_errors`
+    fn messages(&self, listing: &Listing) -> Vec<String> {
+        let mut messages = match self {
+            Report::Valid => vec![],
+            Report::Unparsable(message) => vec![message.clone()],
+            Report::Invalid(errors) if listing.group_by_code => grouped_by_code(errors),
+            Report::Invalid(errors) => errors.iter().map(ValidationError::to_string).collect(),
+        };
+        if let Some(max_errors) = listing.max_errors.filter(|max_errors| messages.len() > *max_errors) {
+            let left_out = messages.len() - max_errors;
+            messages.truncate(max_errors);
+            messages.push(format!("... and {} more", left_out));
+        }
+        messages
+    }
+}
+
+/// the most frequent codes first, like `unknown_field: 4 at /w, /x, /y and 1 more`
+fn grouped_by_code(errors: &[ValidationError]) -> Vec<String> {
+    const EXAMPLES: usize = 3;
+
+    let mut groups: Vec<(ErrorCode, Vec<&str>)> = vec![];
+    for error in errors {
+        match groups.iter_mut().find(|(code, _)| *code == error.code) {
+            Some((_, paths)) => paths.push(&error.path),
+            None => groups.push((error.code, vec![&error.path])),
+        }
+    }
+    groups.sort_by(|(left, left_paths), (right, right_paths)| right_paths.len().cmp(&left_paths.len()).then(left.as_str().cmp(right.as_str())));
+    groups
+        .into_iter()
+        .map(|(code, paths)| {
+            let examples: Vec<&str> = paths.iter().take(EXAMPLES).map(|path| if path.is_empty() { "root" } else { path }).collect();
+            let mut line = format!("{}: {} at {}", code, paths.len(), examples.join(", "));
+            if paths.len() > EXAMPLES {
+                line.push_str(&format!(" and {} more", paths.len() - EXAMPLES));
+            }
+            line
+        })
+        .collect()
+}
+
+fn validate_file(compiled: &CompiledSchema, file: &Path) -> Result<Report, String> {
+    let content = std::fs::read_to_string(file).map_err(|err| format!("cannot read {}: {}", file.display(), err))?;
+    let document: Value = match say::loader::from_str(&content) {
+        Ok(document) => document,
+        Err(err) => return Ok(Report::Unparsable(err.to_string())),
+    };
+    Ok(match compiled.validate_detailed(&document) {
+        Ok(()) => Report::Valid,
+        Err(errors) => Report::Invalid(errors),
+    })
+}
+
+fn print_text(file: &Path, report: &Report, listing: &Listing) {
+    match report {
+        Report::Valid => println!("valid   {}", file.display()),
+        Report::Unparsable(message) => println!("invalid {}: {}", file.display(), message),
+        Report::Invalid(_) => {
+            println!("invalid {}", file.display());
+            for message in report.messages(listing) {
+                println!("  {}", message);
+            }
+        }
+    }
+}
+
+/// a testsuite named after the schema, with a testcase per file and a failure listing its errors
+fn junit(schema: &str, reports: &[(PathBuf, Report)], listing: &Listing) -> String {
+    let failures = reports.iter().filter(|(_, report)| !matches!(report, Report::Valid)).count();
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str(&format!("<testsuite name=\"{}\" tests=\"{}\" failures=\"{}\">\n", escape_xml(schema), reports.len(), failures));
+    for (file, report) in reports {
+        let name = escape_xml(&file.display().to_string());
+        let messages = report.messages(listing);
+        if messages.is_empty() {
+            xml.push_str(&format!("  <testcase name=\"{}\" classname=\"say\"/>\n", name));
+            continue;
+        }
+        xml.push_str(&format!("  <testcase name=\"{}\" classname=\"say\">\n", name));
+        xml.push_str(&format!("    <failure message=\"{} error(s)\">{}</failure>\n", report.error_count(), escape_xml(&messages.join("\n"))));
+        xml.push_str("  </testcase>\n");
+    }
+    xml.push_str("</testsuite>\n");
+    xml
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;").replace('\'', "&apos;")
 }
 
 fn main() {
     let args: Args = Args::from_args();
 
-    if let Some(schema) = args.schema {
-        let content = std::fs::read_to_string(schema).unwrap();
-        let x: Schema = serde_json::from_str(&content).unwrap();
-        dbg!(x);
+    match args.command {
+        Some(Command::Resolve { schema }) => {
+            let compiled = compile_schema(schema).unwrap_or_else(|err| exit_with_error(err, 1));
+            println!("{}", serde_json::to_string_pretty(&compiled.to_schema()).unwrap());
+            return;
+        }
+        Some(Command::Doc { schema }) => {
+            let schema = load_schema(schema).unwrap_or_else(|err| exit_with_error(err, 1));
+            print!("{}", schema.to_markdown());
+            return;
+        }
+        Some(Command::CheckExamples { schema, examples_dir }) => {
+            let all_conform = check_examples(schema, examples_dir).unwrap_or_else(|err| exit_with_error(err, 1));
+            std::process::exit(if all_conform { 0 } else { 1 });
+        }
+        Some(Command::Migrate { schema }) => {
+            let content = std::fs::read_to_string(&schema)
+                .unwrap_or_else(|err| exit_with_error(format!("cannot read {}: {}", schema.display(), err), 1));
+            let mut document: Value = say::loader::from_str(&content)
+                .unwrap_or_else(|err| exit_with_error(format!("invalid schema {}: {}", schema.display(), err), 1));
+            let renamed = say::migrate::migrate(&mut document);
+            if renamed > 0 {
+                if let Err(err) = std::fs::write(&schema, serde_json::to_string_pretty(&document).unwrap() + "\n") {
+                    exit_with_error(format!("cannot write {}: {}", schema.display(), err), 1);
+                }
+            }
+            println!("renamed {} field(s) in {}", renamed, schema.display());
+            return;
+        }
+        None => {}
+    }
+
+    let (usage_error, schema_error) = if args.bitflag_exit { (BITFLAG_USAGE_ERROR, BITFLAG_USAGE_ERROR) } else { (2, 1) };
+    let fixed_schema = match &args.content_type {
+        Some(content_type) => Some(
+            schema_for_content_type(content_type, &args.schema_for, args.schema.as_deref()).unwrap_or_else(|err| exit_with_error(err, usage_error)),
+        ),
+        None => args.schema.clone(),
+    };
+    let suite = match (&fixed_schema, &args.convention) {
+        (Some(schema), _) => schema.clone(),
+        (None, Some(convention)) => convention.spec.clone(),
+        (None, None) => exit_with_error("no schema given, pass one with `--schema <path>` or `--convention <mapping>`", usage_error),
+    };
+    if args.files.is_empty() {
+        exit_with_error("no file given, pass the documents to validate after the options", usage_error);
     }
-}
\ No newline at end of file
+
+    let listing = Listing { group_by_code: args.group_by_code, max_errors: args.max_errors };
+    let mut schemas: HashMap<PathBuf, CompiledSchema> = HashMap::new();
+    let mut code = 0;
+    let mut reports = vec![];
+    for file in args.files {
+        let schema = match (&fixed_schema, &args.convention) {
+            (Some(schema), _) => PathBuf::from(schema),
+            (None, Some(convention)) => convention.schema_for(&file).unwrap_or_else(|| {
+                exit_with_error(format!("{} does not match the convention `{}`", file.display(), convention.spec), usage_error)
+            }),
+            (None, None) => unreachable!("checked above"),
+        };
+        if !schemas.contains_key(&schema) {
+            let compiled = compile_schema(&schema).unwrap_or_else(|err| exit_with_error(err, schema_error));
+            schemas.insert(schema.clone(), compiled);
+        }
+        let report = validate_file(&schemas[&schema], &file).unwrap_or_else(|err| exit_with_error(err, schema_error));
+        code |= report.exit_code(args.bitflag_exit);
+        match args.format {
+            Format::Text => print_text(&file, &report, &listing),
+            Format::Junit => reports.push((file, report)),
+        }
+    }
+    if let Format::Junit = args.format {
+        print!("{}", junit(&suite, &reports, &listing));
+    }
+    std::process::exit(code);
+}
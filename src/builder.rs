@@ -0,0 +1,186 @@
+//! Combinators for building validators in code instead of from a schema document.
+//!
+//! ```
+//! use say::builder::{list_of, string};
+//! use say::schema::DataType;
+//! use say::validator::Validator;
+//! use serde_json::json;
+//!
+//! let names: DataType = list_of(string().max_len(10).matches("[a-z]+")).max(5).into();
+//! assert!(names.validate(&json!(["kilerd", "rust"])));
+//! assert!(!names.validate(&json!(["a very long name"])));
+//! ```
+
+use crate::schema::{BooleanType, DataType, DictType, DynamicType, ListType, LiteralType, NullType, NumberType, OneOfType, RefType, StringType, TupleType};
+
+/// ```
+/// use say::builder::string;
+/// use say::validator::Validator;
+/// use serde_json::json;
+///
+/// let name = string().max_len(10).matches("^[a-z]+$");
+/// assert!(name.validate(&json!("kilerd")));
+/// assert!(!name.validate(&json!("Kilerd")));
+/// assert!(!name.validate(&json!("abcdefghijk")));
+/// ```
+pub fn string() -> StringType {
+    StringType::default()
+}
+
+/// ```
+/// use say::builder::number;
+/// use say::validator::Validator;
+/// use serde_json::json;
+///
+/// assert!(number().validate(&json!(42)));
+/// assert!(!number().validate(&json!("42")));
+///
+/// let score = number().min(0.0).max(100.0);
+/// assert!(score.validate(&json!(100)));
+/// assert!(!score.validate(&json!(100.5)));
+/// ```
+pub fn number() -> NumberType {
+    NumberType::default()
+}
+
+/// ```
+/// use say::builder::boolean;
+/// use say::validator::Validator;
+/// use serde_json::json;
+///
+/// assert!(boolean().validate(&json!(true)));
+/// assert!(!boolean().validate(&json!(1)));
+/// ```
+pub fn boolean() -> BooleanType {
+    BooleanType::default()
+}
+
+/// ```
+/// use say::builder::literal;
+/// use say::validator::Validator;
+/// use serde_json::json;
+///
+/// let level = literal(&["debug", "info"]);
+/// assert!(level.validate(&json!("info")));
+/// assert!(!level.validate(&json!("trace")));
+/// ```
+pub fn literal(candidate: &[&str]) -> LiteralType {
+    LiteralType {
+        candidate: candidate.iter().map(|item| item.to_string()).collect(),
+        ..Default::default()
+    }
+}
+
+/// ```
+/// use say::builder::{list_of, string};
+/// use say::validator::Validator;
+/// use serde_json::json;
+///
+/// let tags = list_of(string()).max(2);
+/// assert!(tags.validate(&json!(["a", "b"])));
+/// assert!(!tags.validate(&json!(["a", "b", "c"])));
+/// assert!(!tags.validate(&json!([1])));
+/// ```
+pub fn list_of(element_type: impl Into<DataType>) -> ListType {
+    ListType {
+        optional: false,
+        nullable: false,
+        features: vec![],
+        element_type: element_type.into(),
+        max_items: None,
+        exact_set: None,
+        element_relation: None,
+        strictly_sorted_by: None,
+        unique_ignoring: None,
+        probability_distribution: None,
+        default: None,
+        description: None,
+    }
+}
+
+/// ```
+/// use say::builder::{boolean, dict};
+/// use say::validator::Validator;
+/// use serde_json::json;
+///
+/// let flags = dict().field("enabled", boolean());
+/// assert!(flags.validate(&json!({ "enabled": true })));
+/// assert!(!flags.validate(&json!({ "disabled": true })));
+/// ```
+pub fn dict() -> DictType {
+    DictType::default()
+}
+
+impl StringType {
+    pub fn max_len(mut self, length: u64) -> Self {
+        self.length = Some(length);
+        self
+    }
+
+    pub fn matches(mut self, regex: &str) -> Self {
+        self.regex = Some(regex.into());
+        self
+    }
+
+    pub fn format(mut self, format: &str) -> Self {
+        self.format = Some(format.to_owned());
+        self
+    }
+}
+
+impl NumberType {
+    pub fn min(mut self, minimum: f64) -> Self {
+        self.minimum = Some(minimum);
+        self
+    }
+
+    pub fn max(mut self, maximum: f64) -> Self {
+        self.maximum = Some(maximum);
+        self
+    }
+}
+
+impl ListType {
+    pub fn max(mut self, max_items: u64) -> Self {
+        self.max_items = Some(max_items);
+        self
+    }
+}
+
+impl DictType {
+    pub fn field(mut self, name: &str, data_type: impl Into<DataType>) -> Self {
+        self.fields.insert(name.to_owned(), data_type.into());
+        self
+    }
+}
+
+macro_rules! into_data_type {
+    ($($variant: ident($ty: ty)),*) => {
+        $(
+            impl From<$ty> for DataType {
+                fn from(inner: $ty) -> Self {
+                    DataType::$variant(Box::new(inner))
+                }
+            }
+        )*
+    };
+}
+
+impl From<DynamicType> for DataType {
+    fn from(inner: DynamicType) -> Self {
+        DataType::Dynamic(inner)
+    }
+}
+
+into_data_type!(
+    Dict(DictType),
+    List(ListType),
+    String(StringType),
+    Literal(LiteralType),
+    Boolean(BooleanType),
+    Number(NumberType),
+    Tuple(TupleType),
+    OneOf(OneOfType),
+    Null(NullType),
+    Ref(RefType)
+);
@@ -0,0 +1,773 @@
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::ops::Deref;
+use std::sync::Arc;
+
+use regex::Regex;
+use serde_json::Value;
+
+use crate::error::{SayError, ValidationError};
+use crate::format::{self, FormatRegistry};
+use crate::loader;
+use crate::schema::{ColumnLimit, DataType, Pattern, Schema};
+use crate::validator::{ValidationOptions, Validator};
+
+/// A [`Schema`] prepared for repeated validation.
+///
+/// Every regex in the schema is compiled once while building the `CompiledSchema`,
+/// and identical patterns share a single compiled [`Regex`].
+#[derive(Debug)]
+pub struct CompiledSchema {
+    schema: Schema,
+    regexes: HashMap<String, Arc<Regex>>,
+    /// what every `Ref` in the schema points at, the refs themselves only hold weak links
+    definitions: HashMap<String, Arc<DataType>>,
+}
+
+impl Schema {
+    /// Check every regex, format and strftime pattern, see [`CompiledSchema::compile`].
+    pub fn compile(self) -> Result<CompiledSchema, CompileError> {
+        CompiledSchema::compile(self)
+    }
+
+    /// The root with its refs linked to a copy of the definitions, for validating without compiling.
+    /// Refs that would not compile, unknown or cyclic ones, are left unlinked and reject every value.
+    pub(crate) fn linked(&self) -> Linked<'_> {
+        let unlinked = || Linked { root: Cow::Borrowed(&self.root), _definitions: HashMap::new() };
+        if self.definitions.is_empty() {
+            return unlinked();
+        }
+        let definitions = share_definitions(&self.definitions);
+        if definitions.values().any(|definition| link(definition, &definitions).is_err()) || find_unbounded_cycle(&definitions).is_some() {
+            return unlinked();
+        }
+        let root = self.root.clone();
+        if link(&root, &definitions).is_err() {
+            return unlinked();
+        }
+        Linked { root: Cow::Owned(root), _definitions: definitions }
+    }
+}
+
+/// A root whose refs stay valid for as long as it is around.
+pub(crate) struct Linked<'a> {
+    root: Cow<'a, DataType>,
+    /// what the refs in `root` point at
+    _definitions: HashMap<String, Arc<DataType>>,
+}
+
+impl Deref for Linked<'_> {
+    type Target = DataType;
+
+    fn deref(&self) -> &DataType {
+        &self.root
+    }
+}
+
+fn share_definitions(definitions: &HashMap<String, DataType>) -> HashMap<String, Arc<DataType>> {
+    definitions.iter().map(|(name, definition)| (name.clone(), Arc::new(definition.clone()))).collect()
+}
+
+fn link(node: &DataType, definitions: &HashMap<String, Arc<DataType>>) -> Result<(), CompileError> {
+    if let DataType::Ref(inner) = node {
+        match definitions.get(&inner.name) {
+            Some(target) => inner.link(target),
+            None => return Err(CompileError::UnknownRef(inner.name.clone())),
+        }
+    }
+    for child in node.children() {
+        link(child, definitions)?;
+    }
+    Ok(())
+}
+
+impl CompiledSchema {
+    pub fn compile(schema: Schema) -> Result<CompiledSchema, CompileError> {
+        CompiledSchema::compile_with(schema, &FormatRegistry::default())
+    }
+
+    /// Compile against a registry of custom formats, any format neither registered
+    /// nor built in is rejected.
+    pub fn compile_with(schema: Schema, formats: &FormatRegistry) -> Result<CompiledSchema, CompileError> {
+        let definitions = share_definitions(&schema.definitions);
+        let mut compiler = Compiler {
+            regexes: HashMap::new(),
+            formats,
+            default_options: ValidationOptions { formats: formats.clone(), ..Default::default() },
+        };
+        // every ref is linked before any default is validated through one
+        let nodes: Vec<&DataType> = std::iter::once(&schema.root).chain(definitions.values().map(Arc::as_ref)).collect();
+        for node in nodes.iter() {
+            link(node, &definitions)?;
+        }
+        if let Some(cycle) = find_unbounded_cycle(&definitions) {
+            return Err(CompileError::CyclicRef(cycle));
+        }
+        for node in nodes.iter() {
+            compiler.compile(node)?;
+        }
+        Ok(CompiledSchema {
+            schema,
+            regexes: compiler.regexes,
+            definitions,
+        })
+    }
+
+    pub fn schema(&self) -> &Schema {
+        &self.schema
+    }
+
+    /// The effective schema after compilation, with every default spelled out and every ref replaced
+    /// by a copy of its definition.
+    ///
+    /// A ref to a recursive definition cannot be inlined, it is kept unlinked along with the definitions
+    /// it needs, so the copy validates like any uncompiled schema.
+    pub fn to_schema(&self) -> Schema {
+        let definitions = &self.schema.definitions;
+        let recursive: HashSet<&str> = definitions.keys().map(String::as_str).filter(|name| is_recursive(name, definitions)).collect();
+        let mut schema = self.schema.clone();
+        inline(&mut schema.root, definitions, &recursive);
+
+        let mut kept = HashMap::new();
+        let mut pending = refs_in(&schema.root);
+        while let Some(name) = pending.pop() {
+            if kept.contains_key(&name) {
+                continue;
+            }
+            let mut definition = definitions[&name].clone();
+            inline(&mut definition, definitions, &recursive);
+            pending.extend(refs_in(&definition));
+            kept.insert(name, definition);
+        }
+        schema.definitions = kept;
+        schema
+    }
+
+    /// the resolved definition a `Ref` with this name points at
+    pub fn definition(&self, name: &str) -> Option<&DataType> {
+        self.definitions.get(name).map(Arc::as_ref)
+    }
+
+    /// the number of distinct regex patterns compiled for this schema
+    pub fn regex_count(&self) -> usize {
+        self.regexes.len()
+    }
+
+    pub fn validate(&self, node: &Value) -> bool {
+        self.schema.root.validate(node)
+    }
+
+    pub fn validate_with(&self, node: &Value, options: &ValidationOptions) -> bool {
+        self.schema.root.validate_with(node, options)
+    }
+
+    pub fn validate_detailed(&self, node: &Value) -> Result<(), Vec<ValidationError>> {
+        self.schema.root.validate_detailed(node)
+    }
+
+    /// Parse and validate a raw document, failing if it is not UTF-8 or not JSON.
+    pub fn validate_bytes(&self, bytes: &[u8]) -> Result<bool, SayError> {
+        let node: Value = loader::from_slice(bytes)?;
+        Ok(self.validate(&node))
+    }
+}
+
+#[derive(Debug)]
+pub enum CompileError {
+    Regex { pattern: String, error: regex::Error },
+    UnknownFormat(String),
+    InvalidColumn(String),
+    InvalidStrftime(String),
+    /// a `default` its own type rejects
+    InvalidDefault(Value),
+    /// a `Ref` to a name missing from the schema's definitions
+    UnknownRef(String),
+    /// definitions that lead back to themselves without descending into the document, first and last being the same,
+    /// so validating against them never terminates
+    CyclicRef(Vec<String>),
+}
+
+impl fmt::Display for CompileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CompileError::Regex { pattern, error } => write!(f, "invalid regex `{}`: {}", pattern, error),
+            CompileError::UnknownFormat(name) => write!(f, "unknown format `{}`", name),
+            CompileError::InvalidColumn(spec) => write!(f, "unsupported column type `{}`", spec),
+            CompileError::InvalidStrftime(pattern) => write!(f, "unsupported strftime pattern `{}`", pattern),
+            CompileError::InvalidDefault(default) => write!(f, "default {} does not match its type", default),
+            CompileError::UnknownRef(name) => write!(f, "no definition named `{}`", name),
+            CompileError::CyclicRef(cycle) => write!(
+                f,
+                "definitions `{}` refer to each other on the same node, put one of the refs inside a dict, list or tuple",
+                cycle.join(" -> ")
+            ),
+        }
+    }
+}
+
+impl std::error::Error for CompileError {}
+
+/// Recursion through dicts, lists and tuples is fine, every step takes validation one level deeper into a finite
+/// document. Only refs checked against the very same node count as edges: a bare alias or a `OneOf` or dynamic variant.
+/// replace every ref to a definition outside `recursive` with a copy of it, inlined in turn
+fn inline(node: &mut DataType, definitions: &HashMap<String, DataType>, recursive: &HashSet<&str>) {
+    if let DataType::Ref(reference) = &*node {
+        if let Some(target) = definitions.get(&reference.name).filter(|_| !recursive.contains(reference.name.as_str())) {
+            let mut inlined = target.clone();
+            if inlined.inherit_ref(reference) {
+                *node = inlined;
+                // the definition may be a ref itself
+                return inline(node, definitions, recursive);
+            }
+        }
+    }
+    for child in node.children_mut() {
+        inline(child, definitions, recursive);
+    }
+}
+
+/// the names of every ref inside the node, without following them
+fn refs_in(node: &DataType) -> Vec<String> {
+    let mut refs = vec![];
+    let mut pending = vec![node];
+    while let Some(node) = pending.pop() {
+        if let DataType::Ref(inner) = node {
+            refs.push(inner.name.clone());
+        }
+        pending.extend(node.children());
+    }
+    refs
+}
+
+/// whether the definition reaches itself through its refs, at any depth
+fn is_recursive(name: &str, definitions: &HashMap<String, DataType>) -> bool {
+    let mut seen = HashSet::new();
+    let mut pending = vec![name.to_owned()];
+    while let Some(current) = pending.pop() {
+        for target in definitions.get(&current).map(refs_in).unwrap_or_default() {
+            if target == name {
+                return true;
+            }
+            if seen.insert(target.clone()) {
+                pending.push(target);
+            }
+        }
+    }
+    false
+}
+
+fn find_unbounded_cycle(definitions: &HashMap<String, Arc<DataType>>) -> Option<Vec<String>> {
+    fn same_node_refs<'a>(node: &'a DataType, refs: &mut Vec<&'a str>) {
+        match node {
+            DataType::Ref(inner) => refs.push(&inner.name),
+            DataType::OneOf(inner) => {
+                for variant in inner.variants.iter() {
+                    same_node_refs(variant, refs);
+                }
+            }
+            DataType::Dynamic(inner) => {
+                for variant in inner.variants.iter() {
+                    same_node_refs(variant, refs);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn visit<'a>(
+        name: &'a str,
+        definitions: &'a HashMap<String, Arc<DataType>>,
+        stack: &mut Vec<&'a str>,
+        done: &mut HashSet<&'a str>,
+    ) -> Option<Vec<String>> {
+        if let Some(start) = stack.iter().position(|visiting| *visiting == name) {
+            let cycle = stack[start..].iter().chain(std::iter::once(&name));
+            return Some(cycle.map(|name| name.to_string()).collect());
+        }
+        if done.contains(name) {
+            return None;
+        }
+        stack.push(name);
+        let mut refs = vec![];
+        same_node_refs(&definitions[name], &mut refs);
+        for target in refs {
+            if let Some(cycle) = visit(target, definitions, stack, done) {
+                return Some(cycle);
+            }
+        }
+        stack.pop();
+        done.insert(name);
+        None
+    }
+
+    let mut names: Vec<&str> = definitions.keys().map(String::as_str).collect();
+    names.sort_unstable();
+    let mut done = HashSet::new();
+    names.into_iter().find_map(|name| visit(name, definitions, &mut vec![], &mut done))
+}
+
+struct Compiler<'a> {
+    regexes: HashMap<String, Arc<Regex>>,
+    formats: &'a FormatRegistry,
+    /// what defaults are validated with, so they may use the registered formats
+    default_options: ValidationOptions,
+}
+
+impl<'a> Compiler<'a> {
+    fn compile(&mut self, node: &DataType) -> Result<(), CompileError> {
+        match node {
+            DataType::Dict(inner) => {
+                for field in inner.fields.values() {
+                    self.compile(field)?;
+                }
+                if let Some(any_fields) = &inner.any_fields {
+                    for (pattern, field) in any_fields.iter() {
+                        self.share(pattern)?;
+                        self.compile(field)?;
+                    }
+                }
+                if let Some(others) = &inner.others {
+                    self.compile(others)?;
+                }
+            }
+            DataType::List(inner) => self.compile(&inner.element_type)?,
+            DataType::Tuple(inner) => {
+                for element in inner.elements.iter().chain(inner.rest.iter()) {
+                    self.compile(element)?;
+                }
+            }
+            DataType::String(inner) => {
+                if let Some(pattern) = &inner.regex {
+                    self.share(pattern)?;
+                }
+                if let Some(name) = &inner.format {
+                    if !self.formats.contains(name) {
+                        return Err(CompileError::UnknownFormat(name.clone()));
+                    }
+                }
+                if let Some(column) = &inner.column {
+                    if ColumnLimit::parse(column).is_none() {
+                        return Err(CompileError::InvalidColumn(column.clone()));
+                    }
+                }
+                if let Some(pattern) = &inner.strftime {
+                    if !format::is_valid_strftime(pattern) {
+                        return Err(CompileError::InvalidStrftime(pattern.clone()));
+                    }
+                }
+            }
+            DataType::OneOf(inner) => {
+                for variant in inner.variants.iter() {
+                    self.compile(variant)?;
+                }
+            }
+            DataType::Dynamic(inner) => {
+                for variant in inner.variants.iter() {
+                    self.compile(variant)?;
+                }
+            }
+            DataType::Literal(_) | DataType::Boolean(_) | DataType::Number(_) | DataType::Null(_) | DataType::Ref(_) => {}
+        }
+        // checked after the children, so a malformed regex is reported as such and not as a rejected default
+        if let Some(default) = node.default_value() {
+            if !node.validate_with(default, &self.default_options) {
+                return Err(CompileError::InvalidDefault(default.clone()));
+            }
+        }
+        Ok(())
+    }
+
+    /// compile the pattern, or hand it the regex already compiled for the same source
+    fn share(&mut self, pattern: &Pattern) -> Result<(), CompileError> {
+        let regex = match self.regexes.get(pattern.as_str()) {
+            Some(regex) => regex.clone(),
+            None => {
+                let regex = pattern.compile().map_err(|error| CompileError::Regex {
+                    pattern: pattern.as_str().to_owned(),
+                    error,
+                })?;
+                let regex = Arc::new(regex);
+                self.regexes.insert(pattern.as_str().to_owned(), regex.clone());
+                regex
+            }
+        };
+        pattern.share(regex);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::compile::{CompileError, CompiledSchema};
+    use crate::error::SayError;
+    use crate::format::FormatRegistry;
+    use crate::schema::{DataType, Schema};
+    use crate::validator::{ValidationOptions, Validator};
+
+    #[test]
+    fn identical_patterns_should_share_one_regex() {
+        let schema: Schema = serde_json::from_value(json!({
+            "root": {
+                "type": "Dict",
+                "fields": {
+                    "a": { "type": "String", "regex": "[0-9]+" },
+                    "b": { "type": "String", "regex": "[0-9]+" },
+                    "c": { "type": "String", "regex": "[a-z]+" }
+                }
+            },
+            "validators": []
+        }))
+        .unwrap();
+        let compiled = CompiledSchema::compile(schema).unwrap();
+        assert_eq!(2, compiled.regex_count());
+
+        let dict = match &compiled.schema().root {
+            DataType::Dict(inner) => inner,
+            _ => unreachable!(),
+        };
+        let regex = |key: &str| match &dict.fields[key] {
+            DataType::String(inner) => inner.regex.as_ref().unwrap().regex().unwrap(),
+            _ => unreachable!(),
+        };
+        assert!(std::ptr::eq(regex("a"), regex("b")));
+        assert!(!std::ptr::eq(regex("a"), regex("c")));
+    }
+
+    #[test]
+    fn any_fields_patterns_should_be_compiled_with_the_rest() {
+        let schema: Schema = serde_json::from_value(json!({
+            "root": {
+                "type": "Dict",
+                "fields": { "code": { "type": "String", "regex": "[a-z]{2}" } },
+                "any_fields": { "[a-z]{2}": { "type": "String" } }
+            },
+            "validators": []
+        }))
+        .unwrap();
+        assert_eq!(1, CompiledSchema::compile(schema).unwrap().regex_count());
+
+        let invalid: Schema = serde_json::from_value(json!({
+            "root": { "type": "Dict", "fields": {}, "any_fields": { "[a-z": { "type": "String" } } },
+            "validators": []
+        }))
+        .unwrap();
+        assert!(matches!(
+            CompiledSchema::compile(invalid),
+            Err(CompileError::Regex { pattern, .. }) if pattern == "[a-z"
+        ));
+    }
+
+    #[test]
+    fn to_schema_should_normalize_defaults() {
+        let schema: Schema = serde_json::from_value(json!({
+            "root": { "type": "List", "element_type": { "type": "Boolean" } },
+            "validators": []
+        }))
+        .unwrap();
+        let compiled = CompiledSchema::compile(schema).unwrap();
+        let resolved = serde_json::to_value(compiled.to_schema()).unwrap();
+        assert_eq!(
+            json!({
+                "root": {
+                    "type": "List",
+                    "optional": false,
+                    "nullable": false,
+                    "features": [],
+                    "default": null,
+                    "description": null,
+                    "element_type": {
+                        "type": "Boolean",
+                        "optional": false,
+                        "nullable": false,
+                        "features": [],
+                        "default": null,
+                        "description": null
+                    },
+                    "max_items": null,
+                    "exact_set": null,
+                    "element_relation": null,
+                    "strictly_sorted_by": null,
+                    "unique_ignoring": null,
+                    "probability_distribution": null
+                },
+                "validators": []
+            }),
+            resolved
+        );
+    }
+
+    #[test]
+    fn to_schema_should_inline_refs() {
+        let schema: Schema = serde_json::from_value(json!({
+            "root": {
+                "type": "Dict",
+                "fields": {
+                    "home": { "type": "Ref", "name": "address" },
+                    "work": { "type": "Ref", "name": "address", "optional": true, "nullable": true },
+                    "tree": { "type": "Ref", "name": "node" }
+                }
+            },
+            "definitions": {
+                "address": { "type": "Dict", "fields": { "city": { "type": "Ref", "name": "city" } } },
+                "city": { "type": "String" },
+                "node": { "type": "List", "element_type": { "type": "Ref", "name": "node" } }
+            },
+            "validators": []
+        }))
+        .unwrap();
+        let resolved = CompiledSchema::compile(schema).unwrap().to_schema();
+
+        let address: Schema = serde_json::from_value(json!({
+            "root": { "type": "Dict", "fields": { "city": { "type": "String" } } },
+            "validators": []
+        }))
+        .unwrap();
+        let fields = match &resolved.root {
+            DataType::Dict(inner) => &inner.fields,
+            _ => unreachable!(),
+        };
+        assert_eq!(&address.root, &fields["home"]);
+        assert!(fields["work"].is_optional());
+        assert!(fields["work"].nullable());
+        // a ref to a recursive definition stays, with only the definition it needs
+        assert!(matches!(&fields["tree"], DataType::Ref(inner) if inner.name == "node"));
+        assert_eq!(vec!["node"], resolved.definitions.keys().collect::<Vec<_>>());
+
+        assert!(resolved.validate(&json!({ "home": { "city": "Shenzhen" }, "work": null, "tree": [[], [[]]] })));
+        assert!(!resolved.validate(&json!({ "home": { "city": 1 }, "tree": [] })));
+    }
+
+    #[test]
+    fn invalid_pattern_should_fail_to_compile() {
+        let schema: Schema = serde_json::from_value(json!({
+            "root": { "type": "Dict", "fields": { "zip": { "type": "String", "regex": "[0-9" } } },
+            "validators": []
+        }))
+        .unwrap();
+        assert!(!schema.validate(&json!({ "zip": "12345" })));
+
+        let error = schema.compile().unwrap_err();
+        assert!(error.to_string().starts_with("invalid regex `[0-9`: "));
+    }
+
+    #[test]
+    fn default_should_match_its_type() {
+        let schema: Schema = serde_json::from_value(json!({
+            "root": {
+                "type": "Dict",
+                "fields": { "port": { "type": "Number", "optional": true, "maximum": 65535, "default": 80 } }
+            },
+            "validators": []
+        }))
+        .unwrap();
+        assert!(CompiledSchema::compile(schema).is_ok());
+
+        let invalid: Schema = serde_json::from_value(json!({
+            "root": {
+                "type": "Dict",
+                "fields": { "port": { "type": "Number", "optional": true, "maximum": 65535, "default": 70000 } }
+            },
+            "validators": []
+        }))
+        .unwrap();
+        assert!(matches!(
+            CompiledSchema::compile(invalid),
+            Err(CompileError::InvalidDefault(default)) if default == json!(70000)
+        ));
+    }
+
+    #[test]
+    fn refs_should_resolve_to_definitions() {
+        let schema: Schema = serde_json::from_value(json!({
+            "root": {
+                "type": "Dict",
+                "fields": {
+                    "home": { "type": "Ref", "name": "address" },
+                    "work": { "type": "Ref", "name": "address", "optional": true }
+                }
+            },
+            "definitions": {
+                "address": { "type": "Dict", "fields": { "city": { "type": "String" }, "zip": { "type": "String", "regex": "[0-9]{6}" } } }
+            },
+            "validators": []
+        }))
+        .unwrap();
+        let invalid = json!({ "home": { "city": "Shenzhen", "zip": "518000" }, "work": { "city": "Beijing", "zip": "1" } });
+        assert!(schema.validate(&json!({ "home": { "city": "Shenzhen", "zip": "518000" } })));
+        assert_eq!("/work/zip", schema.validate_detailed(&invalid).unwrap_err()[0].path);
+
+        let compiled = CompiledSchema::compile(schema).unwrap();
+        assert!(compiled.definition("address").is_some());
+        assert!(compiled.validate(&json!({ "home": { "city": "Shenzhen", "zip": "518000" } })));
+        let errors = compiled.validate_detailed(&invalid).unwrap_err();
+        assert_eq!(1, errors.len());
+        assert_eq!("/work/zip", errors[0].path);
+
+        // the copy keeps working once the compiled schema is gone
+        let copy = compiled.to_schema();
+        drop(compiled);
+        assert!(copy.validate(&json!({ "home": { "city": "Shenzhen", "zip": "518000" } })));
+        assert!(!copy.validate(&invalid));
+    }
+
+    #[test]
+    fn dangling_ref_should_fail_to_compile() {
+        let schema: Schema = serde_json::from_value(json!({
+            "root": { "type": "List", "element_type": { "type": "Ref", "name": "address" } },
+            "definitions": { "adress": { "type": "String" } },
+            "validators": []
+        }))
+        .unwrap();
+        assert!(!schema.validate(&json!(["Shenzhen"])));
+        assert!(matches!(
+            CompiledSchema::compile(schema),
+            Err(CompileError::UnknownRef(name)) if name == "address"
+        ));
+    }
+
+    #[test]
+    fn recursive_definitions_should_validate_nested_documents() {
+        let schema: Schema = serde_json::from_value(json!({
+            "root": { "type": "Ref", "name": "node" },
+            "definitions": {
+                "node": {
+                    "type": "Dict",
+                    "fields": {
+                        "value": { "type": "Number" },
+                        "children": { "type": "List", "element_type": { "type": "Ref", "name": "node" } },
+                        "next": { "type": "Ref", "name": "node", "optional": true }
+                    }
+                }
+            },
+            "validators": []
+        }))
+        .unwrap();
+        let compiled = CompiledSchema::compile(schema).unwrap();
+
+        let tree = json!({
+            "value": 1,
+            "children": [
+                { "value": 2, "children": [] },
+                { "value": 3, "children": [{ "value": 4, "children": [], "next": { "value": 5, "children": [] } }] }
+            ]
+        });
+        assert!(compiled.validate(&tree));
+        let errors = compiled
+            .validate_detailed(&json!({ "value": 1, "children": [{ "value": 2, "children": [{ "value": "3", "children": [] }] }] }))
+            .unwrap_err();
+        assert_eq!(1, errors.len());
+        assert_eq!("/children/0/children/0/value", errors[0].path);
+    }
+
+    #[test]
+    fn unbounded_ref_cycle_should_fail_to_compile() {
+        let variant: Schema = serde_json::from_value(json!({
+            "root": { "type": "Ref", "name": "a" },
+            "definitions": {
+                "a": { "type": "OneOf", "variants": [{ "type": "Ref", "name": "b" }, { "type": "Number" }] },
+                "b": { "type": "Ref", "name": "a", "nullable": true }
+            },
+            "validators": []
+        }))
+        .unwrap();
+        assert!(matches!(
+            CompiledSchema::compile(variant),
+            Err(CompileError::CyclicRef(cycle)) if cycle == ["a", "b", "a"]
+        ));
+
+        let own_variant: Schema = serde_json::from_value(json!({
+            "root": { "type": "Ref", "name": "a" },
+            "definitions": { "a": { "type": "OneOf", "variants": [{ "type": "Ref", "name": "a" }, { "type": "Number" }] } },
+            "validators": []
+        }))
+        .unwrap();
+        assert!(matches!(CompiledSchema::compile(own_variant), Err(CompileError::CyclicRef(cycle)) if cycle == ["a", "a"]));
+
+        let alias: Schema = serde_json::from_value(json!({
+            "root": { "type": "Ref", "name": "self" },
+            "definitions": { "self": { "type": "Ref", "name": "self" } },
+            "validators": []
+        }))
+        .unwrap();
+        assert!(matches!(CompiledSchema::compile(alias), Err(CompileError::CyclicRef(cycle)) if cycle == ["self", "self"]));
+    }
+
+    #[test]
+    fn required_recursion_should_compile() {
+        // no finite document matches, but validating one still terminates
+        let schema: Schema = serde_json::from_value(json!({
+            "root": { "type": "Ref", "name": "a" },
+            "definitions": {
+                "a": { "type": "Dict", "fields": { "b": { "type": "Ref", "name": "b" } } },
+                "b": { "type": "Tuple", "elements": [{ "type": "Ref", "name": "a" }] }
+            },
+            "validators": []
+        }))
+        .unwrap();
+        let compiled = CompiledSchema::compile(schema).unwrap();
+        assert!(!compiled.validate(&json!({ "b": [{ "b": [] }] })));
+    }
+
+    #[test]
+    fn unknown_format_should_fail_to_compile() {
+        let schema: Schema = serde_json::from_value(json!({
+            "root": { "type": "String", "format": "phone" },
+            "validators": []
+        }))
+        .unwrap();
+        assert!(matches!(
+            CompiledSchema::compile(schema),
+            Err(CompileError::UnknownFormat(name)) if name == "phone"
+        ));
+    }
+
+    #[test]
+    fn registered_format_should_be_used_for_validation() {
+        let schema: Schema = serde_json::from_value(json!({
+            "root": { "type": "String", "format": "phone" },
+            "validators": []
+        }))
+        .unwrap();
+        let mut formats = FormatRegistry::new();
+        formats.register("phone", |s| s.starts_with('+') && s[1..].chars().all(|c| c.is_ascii_digit()));
+        let compiled = CompiledSchema::compile_with(schema, &formats).unwrap();
+
+        let options = ValidationOptions { formats, ..Default::default() };
+        assert!(compiled.validate_with(&json!("+8613800000000"), &options));
+        assert!(!compiled.validate_with(&json!("13800000000"), &options));
+    }
+
+    #[test]
+    fn defaults_should_be_checked_against_registered_formats() {
+        let mut formats = FormatRegistry::new();
+        formats.register("phone", |s| s.starts_with('+') && s[1..].chars().all(|c| c.is_ascii_digit()));
+        let schema = |default: &str| -> Schema {
+            serde_json::from_value(json!({
+                "root": { "type": "String", "format": "phone", "optional": true, "default": default },
+                "validators": []
+            }))
+            .unwrap()
+        };
+        assert!(CompiledSchema::compile_with(schema("+8613800000000"), &formats).is_ok());
+        assert!(matches!(
+            CompiledSchema::compile_with(schema("ab"), &formats),
+            Err(CompileError::InvalidDefault(default)) if default == "ab"
+        ));
+    }
+
+    #[test]
+    fn validate_bytes_should_reject_invalid_utf8() {
+        let schema: Schema = serde_json::from_value(json!({
+            "root": { "type": "String" },
+            "validators": []
+        }))
+        .unwrap();
+        let compiled = CompiledSchema::compile(schema).unwrap();
+        assert!(compiled.validate_bytes(b"\"abc\"").unwrap());
+        assert!(!compiled.validate_bytes(b"123").unwrap());
+        assert!(matches!(compiled.validate_bytes(b"\"a\xc3\""), Err(SayError::InvalidUtf8 { offset: 2 })));
+    }
+}
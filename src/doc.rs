@@ -0,0 +1,194 @@
+//! Rendering a schema as Markdown for people consuming the documents it describes.
+
+use std::fmt::Write;
+
+use crate::schema::{DataType, Schema};
+use crate::validator::{at_least_properties, Validator};
+
+impl Schema {
+    /// One table row per dict field, nested dicts and lists of dicts included, named by their path
+    /// like `address.city` or `friends[].name`. Every definition gets a table of its own.
+    ///
+    /// A root that is not a dict is documented by a single row named `.`.
+    pub fn to_markdown(&self) -> String {
+        let mut markdown = String::new();
+        table(&mut markdown, &self.root);
+        let mut definitions: Vec<_> = self.definitions.iter().collect();
+        definitions.sort_by_key(|(name, _)| name.as_str());
+        for (name, definition) in definitions {
+            let _ = write!(markdown, "\n## {}\n\n", name);
+            table(&mut markdown, definition);
+        }
+        markdown
+    }
+}
+
+fn table(markdown: &mut String, root: &DataType) {
+    markdown.push_str("| Field | Type | Required | Constraints | Description |\n");
+    markdown.push_str("|-------|------|----------|-------------|-------------|\n");
+    let mut rows = vec![];
+    match root {
+        DataType::Dict(_) | DataType::List(_) => nested(root, "", &mut rows),
+        _ => rows.push(row(".", root, true)),
+    }
+    for row in rows {
+        markdown.push_str(&row);
+    }
+}
+
+fn nested(data_type: &DataType, path: &str, rows: &mut Vec<String>) {
+    match data_type {
+        DataType::Dict(inner) => {
+            let mut fields: Vec<_> = inner.fields.iter().collect();
+            fields.sort_by_key(|(key, _)| key.as_str());
+            for (key, field) in fields {
+                let field_path = if path.is_empty() { key.clone() } else { format!("{}.{}", path, key) };
+                rows.push(row(&field_path, field, !(inner.all_optional || field.is_optional())));
+                nested(field, &field_path, rows);
+            }
+        }
+        DataType::List(inner) => nested(&inner.element_type, &format!("{}[]", path), rows),
+        _ => {}
+    }
+}
+
+fn row(path: &str, data_type: &DataType, required: bool) -> String {
+    let mut kind = kind(data_type);
+    if data_type.nullable() {
+        kind.push_str(", nullable");
+    }
+    format!(
+        "| `{}` | {} | {} | {} | {} |\n",
+        path,
+        cell(&kind),
+        if required { "yes" } else { "no" },
+        cell(&constraints(data_type).join(", ")),
+        cell(data_type.description().unwrap_or_default()),
+    )
+}
+
+fn kind(data_type: &DataType) -> String {
+    match data_type {
+        DataType::Dict(_) => "Dict".to_owned(),
+        DataType::List(inner) => format!("List of {}", kind(&inner.element_type)),
+        DataType::String(_) => "String".to_owned(),
+        DataType::Literal(_) => "Literal".to_owned(),
+        DataType::Boolean(_) => "Boolean".to_owned(),
+        DataType::Number(_) => "Number".to_owned(),
+        DataType::Tuple(_) => "Tuple".to_owned(),
+        DataType::OneOf(inner) => inner.variants.iter().map(kind).collect::<Vec<_>>().join(" or "),
+        DataType::Null(_) => "Null".to_owned(),
+        DataType::Ref(inner) => format!("[{}](#{})", inner.name, inner.name.to_lowercase()),
+        DataType::Dynamic(_) => "Dynamic".to_owned(),
+    }
+}
+
+/// the common constraints only, the schema itself stays the reference for the rest
+fn constraints(data_type: &DataType) -> Vec<String> {
+    let mut constraints = vec![];
+    match data_type {
+        DataType::Dict(inner) => {
+            if let Some(min) = inner.min_properties {
+                constraints.push(at_least_properties(min));
+            }
+            if let Some(max) = inner.max_properties {
+                constraints.push(format!("at most {} properties", max));
+            }
+        }
+        DataType::List(inner) => {
+            if let Some(max) = inner.max_items {
+                constraints.push(format!("at most {} items", max));
+            }
+        }
+        DataType::String(inner) => {
+            if let Some(min) = inner.min_length {
+                constraints.push(format!("at least {} {}", min, inner.length_unit.name()));
+            }
+            if let Some(max) = inner.length {
+                constraints.push(format!("at most {} {}", max, inner.length_unit.name()));
+            }
+            if let Some(regex) = &inner.regex {
+                constraints.push(format!("matches `{}`", regex.as_str()));
+            }
+            if let Some(format) = &inner.format {
+                constraints.push(format!("format `{}`", format));
+            }
+        }
+        DataType::Literal(inner) => {
+            let candidates: Vec<_> = inner.candidate.iter().map(|candidate| format!("`{}`", candidate)).collect();
+            constraints.push(format!("one of {}", candidates.join(", ")));
+        }
+        DataType::Number(inner) => {
+            if inner.integer {
+                constraints.push("integer".to_owned());
+            }
+            if let Some(min) = inner.minimum {
+                constraints.push(format!("{} {}", if inner.exclusive_minimum { ">" } else { ">=" }, min));
+            }
+            if let Some(max) = inner.maximum {
+                constraints.push(format!("{} {}", if inner.exclusive_maximum { "<" } else { "<=" }, max));
+            }
+            if let Some(candidate) = &inner.candidate {
+                let candidates: Vec<_> = candidate.iter().map(f64::to_string).collect();
+                constraints.push(format!("one of {}", candidates.join(", ")));
+            }
+        }
+        DataType::Tuple(inner) => constraints.push(format!("{} elements", inner.elements.len())),
+        DataType::Boolean(_) | DataType::OneOf(_) | DataType::Null(_) | DataType::Ref(_) | DataType::Dynamic(_) => {}
+    }
+    if let Some(default) = data_type.default_value() {
+        constraints.push(format!("defaults to `{}`", default));
+    }
+    constraints
+}
+
+/// keeps `|` and line breaks from ending the table cell early
+fn cell(content: &str) -> String {
+    content.replace('|', "\\|").replace('\n', " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::schema::Schema;
+
+    #[test]
+    fn markdown_should_have_a_row_per_field() {
+        let schema: Schema = serde_json::from_value(json!({
+            "root": {
+                "type": "Dict",
+                "fields": {
+                    "name": { "type": "String", "length": 10, "regex": "^[a-z]+$", "description": "the login name" },
+                    "age": { "type": "Number", "optional": true, "minimum": 0, "integer": true },
+                    "level": { "type": "Literal", "candidate": ["debug", "info"], "nullable": true },
+                    "friends": {
+                        "type": "List",
+                        "max_items": 5,
+                        "element_type": { "type": "Dict", "fields": { "name": { "type": "String", "description": "a | b" } } }
+                    },
+                    "home": { "type": "Ref", "name": "address" }
+                }
+            },
+            "definitions": {
+                "address": { "type": "Dict", "fields": { "city": { "type": "String", "min_length": 1 } } }
+            },
+            "validators": []
+        }))
+        .unwrap();
+        let markdown = schema.to_markdown();
+
+        for row in [
+            "| `age` | Number | no | integer, >= 0 |  |",
+            "| `friends` | List of Dict | yes | at most 5 items |  |",
+            "| `friends[].name` | String | yes |  | a \\| b |",
+            "| `home` | [address](#address) | yes |  |  |",
+            "| `level` | Literal, nullable | yes | one of `debug`, `info` |  |",
+            "| `name` | String | yes | at most 10 characters, matches `^[a-z]+$` | the login name |",
+            "## address",
+            "| `city` | String | yes | at least 1 characters |  |",
+        ] {
+            assert!(markdown.lines().any(|line| line == row), "missing {:?} in\n{}", row, markdown);
+        }
+    }
+}
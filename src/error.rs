@@ -0,0 +1,385 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+#[derive(Debug)]
+pub enum SayError {
+    /// the input is not UTF-8, `offset` is the index of the first offending byte
+    InvalidUtf8 { offset: usize },
+    Json(serde_json::Error),
+}
+
+impl fmt::Display for SayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SayError::InvalidUtf8 { offset } => write!(f, "invalid UTF-8 at byte {}", offset),
+            SayError::Json(err) => write!(f, "invalid JSON: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for SayError {}
+
+impl From<serde_json::Error> for SayError {
+    fn from(err: serde_json::Error) -> Self {
+        SayError::Json(err)
+    }
+}
+
+/// Every kind of validation failure, written in snake case like `missing_field`.
+///
+/// Codes are stable: a released code keeps its name and meaning, new ones are only ever added.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorCode {
+    /// the document has more nodes than `max_nodes` allows
+    TooManyNodes,
+    /// the node is not of the declared type
+    WrongType,
+    TooManyProperties,
+    TooFewProperties,
+    /// the keys of an `integer_key_sequence` dict are not `"0"` to `"n-1"`
+    NonSequentialKeys,
+    /// two values of a `unique_values` dict are equal
+    DuplicateValue,
+    /// the key set matches none or several of the `shapes`
+    NoMatchingShape,
+    /// a `length_of` count differs from its array's length
+    LengthMismatch,
+    ConflictingField,
+    /// a key is neither declared nor matched by `any_fields` or `others`
+    UnknownField,
+    /// a required field is absent
+    MissingField,
+    TooManyItems,
+    NotExactSet,
+    /// an element breaks the `element_relation` to the one before it
+    RelationViolated,
+    NotStrictlySorted,
+    /// an element equals an earlier one apart from the `unique_ignoring` keys
+    DuplicateItem,
+    /// a literal or property name is not among the candidates
+    NotACandidate,
+    TooLong,
+    TooShort,
+    SurroundingWhitespace,
+    /// the string does not match the `regex`
+    PatternMismatch,
+    /// the value is not valid for its string or number `format`
+    InvalidFormat,
+    ExceedsColumn,
+    StrftimeMismatch,
+    UnsafeInteger,
+    NotAnInteger,
+    /// the number loses precision as `f32`
+    NotF32,
+    BelowMinimum,
+    AboveMaximum,
+    /// the number is not on the `grid`
+    OffGrid,
+    /// a `graph_integrity` edge names a node the dict does not declare
+    DanglingReference,
+    /// an acyclic `graph_integrity` graph has a cycle
+    Cycle,
+    /// a tuple has fewer elements than it declares, or more without a `rest` type
+    WrongLength,
+    /// a `probability_distribution` does not add up to 1
+    NotADistribution,
+    /// no variant of a `OneOf` accepts the value, the errors of each variant follow
+    NoMatchingVariant,
+    /// the document serializes to more than `max_serialized_bytes`
+    TooLarge,
+}
+
+impl ErrorCode {
+    pub const ALL: &'static [ErrorCode] = &[
+        ErrorCode::TooManyNodes,
+        ErrorCode::WrongType,
+        ErrorCode::TooManyProperties,
+        ErrorCode::TooFewProperties,
+        ErrorCode::NonSequentialKeys,
+        ErrorCode::DuplicateValue,
+        ErrorCode::NoMatchingShape,
+        ErrorCode::LengthMismatch,
+        ErrorCode::ConflictingField,
+        ErrorCode::UnknownField,
+        ErrorCode::MissingField,
+        ErrorCode::TooManyItems,
+        ErrorCode::NotExactSet,
+        ErrorCode::RelationViolated,
+        ErrorCode::NotStrictlySorted,
+        ErrorCode::DuplicateItem,
+        ErrorCode::NotACandidate,
+        ErrorCode::TooLong,
+        ErrorCode::TooShort,
+        ErrorCode::SurroundingWhitespace,
+        ErrorCode::PatternMismatch,
+        ErrorCode::InvalidFormat,
+        ErrorCode::ExceedsColumn,
+        ErrorCode::StrftimeMismatch,
+        ErrorCode::UnsafeInteger,
+        ErrorCode::NotAnInteger,
+        ErrorCode::NotF32,
+        ErrorCode::BelowMinimum,
+        ErrorCode::AboveMaximum,
+        ErrorCode::OffGrid,
+        ErrorCode::DanglingReference,
+        ErrorCode::Cycle,
+        ErrorCode::WrongLength,
+        ErrorCode::NotADistribution,
+        ErrorCode::NoMatchingVariant,
+        ErrorCode::TooLarge,
+    ];
+
+    pub fn category(&self) -> ErrorCategory {
+        match self {
+            ErrorCode::WrongType => ErrorCategory::Type,
+            ErrorCode::MissingField => ErrorCategory::Missing,
+            ErrorCode::TooManyNodes => ErrorCategory::Constraint,
+            ErrorCode::TooManyProperties => ErrorCategory::Constraint,
+            ErrorCode::TooFewProperties => ErrorCategory::Constraint,
+            ErrorCode::NonSequentialKeys => ErrorCategory::Constraint,
+            ErrorCode::DuplicateValue => ErrorCategory::Constraint,
+            ErrorCode::NoMatchingShape => ErrorCategory::Constraint,
+            ErrorCode::LengthMismatch => ErrorCategory::Constraint,
+            ErrorCode::ConflictingField => ErrorCategory::Constraint,
+            ErrorCode::UnknownField => ErrorCategory::Constraint,
+            ErrorCode::TooManyItems => ErrorCategory::Constraint,
+            ErrorCode::NotExactSet => ErrorCategory::Constraint,
+            ErrorCode::RelationViolated => ErrorCategory::Constraint,
+            ErrorCode::NotStrictlySorted => ErrorCategory::Constraint,
+            ErrorCode::DuplicateItem => ErrorCategory::Constraint,
+            ErrorCode::NotACandidate => ErrorCategory::Constraint,
+            ErrorCode::TooLong => ErrorCategory::Constraint,
+            ErrorCode::TooShort => ErrorCategory::Constraint,
+            ErrorCode::SurroundingWhitespace => ErrorCategory::Constraint,
+            ErrorCode::PatternMismatch => ErrorCategory::Constraint,
+            ErrorCode::InvalidFormat => ErrorCategory::Constraint,
+            ErrorCode::ExceedsColumn => ErrorCategory::Constraint,
+            ErrorCode::StrftimeMismatch => ErrorCategory::Constraint,
+            ErrorCode::UnsafeInteger => ErrorCategory::Constraint,
+            ErrorCode::NotAnInteger => ErrorCategory::Constraint,
+            ErrorCode::NotF32 => ErrorCategory::Constraint,
+            ErrorCode::BelowMinimum => ErrorCategory::Constraint,
+            ErrorCode::AboveMaximum => ErrorCategory::Constraint,
+            ErrorCode::OffGrid => ErrorCategory::Constraint,
+            ErrorCode::DanglingReference => ErrorCategory::Constraint,
+            ErrorCode::Cycle => ErrorCategory::Constraint,
+            ErrorCode::WrongLength => ErrorCategory::Constraint,
+            ErrorCode::NotADistribution => ErrorCategory::Constraint,
+            ErrorCode::NoMatchingVariant => ErrorCategory::Constraint,
+            ErrorCode::TooLarge => ErrorCategory::Constraint,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorCode::TooManyNodes => "too_many_nodes",
+            ErrorCode::WrongType => "wrong_type",
+            ErrorCode::TooManyProperties => "too_many_properties",
+            ErrorCode::TooFewProperties => "too_few_properties",
+            ErrorCode::NonSequentialKeys => "non_sequential_keys",
+            ErrorCode::DuplicateValue => "duplicate_value",
+            ErrorCode::NoMatchingShape => "no_matching_shape",
+            ErrorCode::LengthMismatch => "length_mismatch",
+            ErrorCode::ConflictingField => "conflicting_field",
+            ErrorCode::UnknownField => "unknown_field",
+            ErrorCode::MissingField => "missing_field",
+            ErrorCode::TooManyItems => "too_many_items",
+            ErrorCode::NotExactSet => "not_exact_set",
+            ErrorCode::RelationViolated => "relation_violated",
+            ErrorCode::NotStrictlySorted => "not_strictly_sorted",
+            ErrorCode::DuplicateItem => "duplicate_item",
+            ErrorCode::NotACandidate => "not_a_candidate",
+            ErrorCode::TooLong => "too_long",
+            ErrorCode::TooShort => "too_short",
+            ErrorCode::SurroundingWhitespace => "surrounding_whitespace",
+            ErrorCode::PatternMismatch => "pattern_mismatch",
+            ErrorCode::InvalidFormat => "invalid_format",
+            ErrorCode::ExceedsColumn => "exceeds_column",
+            ErrorCode::StrftimeMismatch => "strftime_mismatch",
+            ErrorCode::UnsafeInteger => "unsafe_integer",
+            ErrorCode::NotAnInteger => "not_an_integer",
+            ErrorCode::NotF32 => "not_f32",
+            ErrorCode::BelowMinimum => "below_minimum",
+            ErrorCode::AboveMaximum => "above_maximum",
+            ErrorCode::OffGrid => "off_grid",
+            ErrorCode::DanglingReference => "dangling_reference",
+            ErrorCode::Cycle => "cycle",
+            ErrorCode::WrongLength => "wrong_length",
+            ErrorCode::NotADistribution => "not_a_distribution",
+            ErrorCode::NoMatchingVariant => "no_matching_variant",
+            ErrorCode::TooLarge => "too_large",
+        }
+    }
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// as its snake case name, like `"missing_field"`
+impl Serialize for ErrorCode {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for ErrorCode {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// A coarse grouping of error codes, for callers that only care which kind of thing went wrong.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorCategory {
+    /// the node has the wrong JSON type
+    Type,
+    /// the node has the right type but breaks one of its constraints
+    Constraint,
+    /// a required field is absent
+    Missing,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownErrorCode(pub String);
+
+impl fmt::Display for UnknownErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown error code `{}`", self.0)
+    }
+}
+
+impl std::error::Error for UnknownErrorCode {}
+
+impl FromStr for ErrorCode {
+    type Err = UnknownErrorCode;
+
+    fn from_str(code: &str) -> Result<Self, Self::Err> {
+        ErrorCode::ALL
+            .iter()
+            .copied()
+            .find(|candidate| candidate.as_str() == code)
+            .ok_or_else(|| UnknownErrorCode(code.to_owned()))
+    }
+}
+
+/// One reason a document failed validation.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ValidationError {
+    pub code: ErrorCode,
+    /// JSON pointer to the offending node, empty for the root
+    pub path: String,
+    /// the type or constraint the node had to satisfy
+    pub expected: String,
+    /// the node as found, `null` when a required field is missing,
+    /// large arrays and objects are cut down to their first entries
+    pub actual: serde_json::Value,
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let path = if self.path.is_empty() { "root" } else { &self.path };
+        write!(f, "{}: expected {}, found {}", path, self.expected, self.actual)
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+/// The errors of one element of a heterogeneous array.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ElementErrors {
+    pub index: usize,
+    /// the discriminator value whose schema the element was checked against,
+    /// `None` when it fell back to the calling schema
+    pub schema: Option<String>,
+    /// with paths starting at the array, like `/3/email`
+    pub errors: Vec<ValidationError>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum HeterogeneousError {
+    NotAnArray(serde_json::Value),
+    Elements(Vec<ElementErrors>),
+}
+
+impl fmt::Display for HeterogeneousError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HeterogeneousError::NotAnArray(value) => write!(f, "root: expected an array, found {}", value),
+            HeterogeneousError::Elements(elements) => {
+                for (position, element) in elements.iter().enumerate() {
+                    if position > 0 {
+                        writeln!(f)?;
+                    }
+                    let schema = element.schema.as_deref().unwrap_or("default");
+                    write!(f, "element {} ({} schema):", element.index, schema)?;
+                    for error in element.errors.iter() {
+                        write!(f, "\n  {}", error)?;
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for HeterogeneousError {}
+
+/// Validation errors arranged like the document they were found in, as a form shows them next to their fields.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ErrorTree {
+    /// the errors of this node itself
+    pub errors: Vec<ValidationError>,
+    /// keyed by object key or array index, unescaped from the JSON pointer
+    pub children: BTreeMap<String, ErrorTree>,
+}
+
+impl ErrorTree {
+    /// Place every error at the node its path points to, in the order given.
+    pub fn from_errors(errors: Vec<ValidationError>) -> ErrorTree {
+        let mut tree = ErrorTree::default();
+        for error in errors {
+            let mut node = &mut tree;
+            for segment in error.path.split('/').skip(1) {
+                let segment = segment.replace("~1", "/").replace("~0", "~");
+                node = node.children.entry(segment).or_default();
+            }
+            node.errors.push(error);
+        }
+        tree
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.errors.is_empty() && self.children.is_empty()
+    }
+
+    /// the subtree of an object key or array index
+    pub fn child(&self, segment: &str) -> Option<&ErrorTree> {
+        self.children.get(segment)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::{ErrorCategory, ErrorCode, UnknownErrorCode};
+
+    #[test]
+    fn error_codes_should_round_trip_through_strings() {
+        for code in ErrorCode::ALL {
+            assert_eq!(Ok(*code), code.to_string().parse());
+        }
+        assert_eq!(Err(UnknownErrorCode("nope".to_owned())), "nope".parse::<ErrorCode>());
+    }
+
+    #[test]
+    fn error_codes_should_fall_into_categories() {
+        assert_eq!(ErrorCategory::Type, ErrorCode::WrongType.category());
+        assert_eq!(ErrorCategory::Missing, ErrorCode::MissingField.category());
+        assert_eq!(ErrorCategory::Constraint, ErrorCode::TooLong.category());
+    }
+}
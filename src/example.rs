@@ -0,0 +1,184 @@
+//! Generating documents from a schema.
+
+use serde_json::{json, Value};
+
+use crate::schema::{DataType, Schema};
+use crate::validator::Validator;
+
+impl Schema {
+    /// A starting point for writing a document by hand.
+    ///
+    /// Required fields are filled in, lists are empty, literals take their first candidate and numbers
+    /// their first candidate or `minimum`. Other constraints, like string lengths or regexes, are not looked at.
+    pub fn sample(&self) -> Value {
+        self.linked().sample()
+    }
+
+    /// A document the schema rejects because its root has the wrong type.
+    ///
+    /// Every other constraint is left alone, so handlers see the plainest failure there is.
+    /// `None` when the root accepts a value of every type, like a `OneOf` covering them all.
+    pub fn counterexample(&self) -> Option<Value> {
+        let candidates = vec![json!(false), json!(0), json!(""), json!([]), json!({}), Value::Null];
+        let root = self.linked();
+        let accepted = |candidate: &Value| (candidate.is_null() && root.nullable()) || root.validate_type(candidate);
+        candidates.into_iter().find(|candidate| !accepted(candidate))
+    }
+}
+
+impl DataType {
+    fn sample(&self) -> Value {
+        self.sample_within(&mut vec![])
+    }
+
+    /// `refs` are the definitions being sampled further up, a ref back to one of them becomes `null`
+    /// instead of recursing forever
+    fn sample_within(&self, refs: &mut Vec<String>) -> Value {
+        match self {
+            DataType::Dict(inner) => {
+                let fields = inner
+                    .fields
+                    .iter()
+                    .filter(|(_, field)| !(inner.all_optional || field.is_optional()))
+                    .map(|(key, field)| (key.clone(), field.sample_within(refs)));
+                Value::Object(fields.collect())
+            }
+            DataType::List(_) => json!([]),
+            DataType::Tuple(inner) => Value::Array(inner.elements.iter().map(|element| element.sample_within(refs)).collect()),
+            DataType::String(_) => json!(""),
+            DataType::Literal(inner) => inner.candidate.first().map_or(Value::Null, |candidate| json!(candidate)),
+            DataType::Boolean(_) => json!(false),
+            DataType::Null(_) => Value::Null,
+            DataType::Number(inner) => {
+                let first = inner.candidate.as_ref().and_then(|candidate| candidate.first());
+                json!(first.or(inner.minimum.as_ref()).copied().unwrap_or(0.0))
+            }
+            DataType::OneOf(inner) => inner.variants.first().map_or(Value::Null, |variant| variant.sample_within(refs)),
+            DataType::Ref(inner) => match inner.target() {
+                Some(target) if !refs.contains(&inner.name) => {
+                    refs.push(inner.name.clone());
+                    let sample = target.sample_within(refs);
+                    refs.pop();
+                    sample
+                }
+                _ => Value::Null,
+            },
+            DataType::Dynamic(inner) => inner.variants.first().map_or(Value::Null, |variant| variant.sample_within(refs)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::error::ErrorCode;
+    use crate::schema::Schema;
+
+    fn schema(root: serde_json::Value) -> Schema {
+        serde_json::from_value(json!({ "root": root, "validators": [] })).unwrap()
+    }
+
+    #[test]
+    fn counterexample_should_fail_validation() {
+        let schemas = vec![
+            schema(json!({ "type": "Boolean" })),
+            schema(json!({ "type": "Number", "nullable": true })),
+            schema(json!({ "type": "String", "length": 3 })),
+            schema(json!({ "type": "Literal", "candidate": ["a"] })),
+            schema(json!({ "type": "List", "element_type": { "type": "Boolean" } })),
+            schema(json!({ "type": "Dict", "fields": { "name": { "type": "String" } } })),
+            schema(json!({ "type": "Number", "minimum": 5 })),
+        ];
+        for schema in schemas {
+            let counterexample = schema.counterexample().unwrap();
+            let errors = schema.validate_detailed(&counterexample).unwrap_err();
+            assert_eq!(vec![ErrorCode::WrongType], errors.iter().map(|error| error.code).collect::<Vec<_>>(), "{:?}", counterexample);
+        }
+
+        let anything = schema(json!({
+            "type": "OneOf",
+            "variants": [
+                { "type": "Boolean" },
+                { "type": "Number" },
+                { "type": "String" },
+                { "type": "List", "element_type": { "type": "Null" } },
+                { "type": "Dict", "fields": {} },
+                { "type": "Null" }
+            ]
+        }));
+        assert_eq!(None, anything.counterexample());
+    }
+
+    #[test]
+    fn sample_should_stop_at_recursive_refs() {
+        let schema: Schema = serde_json::from_value(json!({
+            "root": { "type": "Ref", "name": "a" },
+            "definitions": {
+                "a": {
+                    "type": "OneOf",
+                    "variants": [{ "type": "Dict", "fields": { "v": { "type": "Ref", "name": "a" } } }, { "type": "Null" }]
+                }
+            },
+            "validators": []
+        }))
+        .unwrap();
+        let compiled = schema.compile().unwrap();
+        let sample = compiled.schema().sample();
+        assert_eq!(json!({ "v": null }), sample);
+        assert!(compiled.validate(&sample));
+    }
+
+    #[test]
+    fn sample_should_follow_refs_without_compiling() {
+        let schema: Schema = serde_json::from_value(json!({
+            "root": { "type": "Dict", "fields": { "home": { "type": "Ref", "name": "address" } } },
+            "definitions": {
+                "address": { "type": "Dict", "fields": { "city": { "type": "String" } } }
+            },
+            "validators": []
+        }))
+        .unwrap();
+        let sample = schema.sample();
+        assert_eq!(json!({ "home": { "city": "" } }), sample);
+        assert!(schema.validate(&sample));
+
+        let boolean: Schema = serde_json::from_value(json!({
+            "root": { "type": "Ref", "name": "flag" },
+            "definitions": { "flag": { "type": "Boolean" } },
+            "validators": []
+        }))
+        .unwrap();
+        let counterexample = boolean.counterexample().unwrap();
+        assert_eq!(json!(0), counterexample);
+        assert!(!boolean.validate(&counterexample));
+    }
+
+    #[test]
+    fn sample_should_pick_the_first_candidate() {
+        let schema = schema(json!({
+            "type": "Dict",
+            "fields": {
+                "level": { "type": "Literal", "candidate": ["info", "debug", "trace"] },
+                "note": { "type": "String", "optional": true }
+            }
+        }));
+        let sample = schema.sample();
+        assert_eq!(json!({ "level": "info" }), sample);
+        assert!(schema.validate(&sample));
+
+        let errors = schema.validate_detailed(&json!({ "level": "warn" })).unwrap_err();
+        assert_eq!(ErrorCode::NotACandidate, errors[0].code);
+        assert_eq!("one of info, debug, trace", errors[0].expected);
+
+        let through_ref: Schema = serde_json::from_value(json!({
+            "root": { "type": "Dict", "fields": { "level": { "type": "Ref", "name": "level" } } },
+            "definitions": { "level": { "type": "Literal", "candidate": ["info", "debug", "trace"] } },
+            "validators": []
+        }))
+        .unwrap();
+        let sample = through_ref.sample();
+        assert_eq!(json!({ "level": "info" }), sample);
+        assert!(through_ref.validate(&sample));
+    }
+}
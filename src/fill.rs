@@ -0,0 +1,431 @@
+//! Normalizing documents against a schema.
+
+use serde_json::Value;
+
+use crate::schema::{DataType, Schema};
+use crate::validator::Validator;
+
+/// The outcome of filling a document against a schema.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Filled {
+    pub value: Value,
+    /// JSON pointers to the optional fields the document left out, sorted
+    pub absent: Vec<String>,
+}
+
+/// How [`Schema::fill_with`] rewrites a document before normalizing it.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FillOptions {
+    /// a `null` where a list is declared becomes `[]`, for serializers writing empty arrays as `null`
+    pub null_as_empty_array: bool,
+    /// a `null` where a dict is declared becomes `{}`
+    pub null_as_empty_object: bool,
+}
+
+impl Schema {
+    /// Normalize the document like [`Schema::strip_unknown`], recording every optional field it omits.
+    pub fn fill(&self, node: &Value) -> Filled {
+        self.fill_with(node, &FillOptions::default())
+    }
+
+    /// Like [`Schema::fill`], coercing `null`s first as the options ask.
+    /// The filled value is what to validate, a coerced `{}` still has to have its required fields.
+    pub fn fill_with(&self, node: &Value, options: &FillOptions) -> Filled {
+        let root = self.linked();
+        let mut value = node.clone();
+        root.coerce_nulls(&mut value, options);
+        root.strip_unknown(&mut value);
+        let mut absent = vec![];
+        root.collect_absent(&value, "", &mut absent);
+        absent.sort();
+        Filled { value, absent }
+    }
+
+    /// Insert the declared `default` of every absent optional dict field, recursively,
+    /// fields already present are left as they are.
+    pub fn apply_defaults(&self, node: &mut Value) {
+        self.linked().apply_defaults(node);
+    }
+
+    /// Return a copy of the document with every key the schema does not declare removed,
+    /// recursively through dicts and lists.
+    pub fn strip_unknown(&self, node: &Value) -> Value {
+        let mut node = node.clone();
+        self.linked().strip_unknown(&mut node);
+        node
+    }
+}
+
+impl DataType {
+    pub fn strip_unknown(&self, node: &mut Value) {
+        match (self, node) {
+            (DataType::Dict(inner), Value::Object(object)) => {
+                let matches_any_field = |key: &str| {
+                    inner.any_fields.iter().flatten().any(|(pattern, _)| pattern.is_match(key))
+                };
+                if inner.others.is_none() {
+                    object.retain(|key, _| inner.fields.contains_key(key) || matches_any_field(key));
+                }
+                for (key, value) in object.iter_mut() {
+                    match inner.fields.get(key) {
+                        Some(field) => field.strip_unknown(value),
+                        None if matches_any_field(key) => {
+                            for (pattern, field) in inner.any_fields.iter().flatten() {
+                                if pattern.is_match(key) {
+                                    field.strip_unknown(value);
+                                }
+                            }
+                        }
+                        None => {
+                            if let Some(others) = &inner.others {
+                                others.strip_unknown(value);
+                            }
+                        }
+                    }
+                }
+            }
+            (DataType::List(inner), Value::Array(array)) => {
+                for item in array.iter_mut() {
+                    inner.element_type.strip_unknown(item);
+                }
+            }
+            (DataType::Tuple(inner), Value::Array(array)) => {
+                for (index, item) in array.iter_mut().enumerate() {
+                    if let Some(element) = inner.element(index) {
+                        element.strip_unknown(item);
+                    }
+                }
+            }
+            (DataType::OneOf(inner), node) => {
+                // unknown keys would make a dict variant reject the node, so the first variant of the right type is taken
+                if let Some(variant) = inner.variants.iter().find(|variant| variant.validate_type(node)) {
+                    variant.strip_unknown(node);
+                }
+            }
+            (DataType::Ref(inner), node) => {
+                if let Some(target) = inner.target() {
+                    target.strip_unknown(node);
+                }
+            }
+            (DataType::Dynamic(inner), node) => {
+                if let Some(variant) = inner.select(node) {
+                    variant.strip_unknown(node);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn coerce_nulls(&self, node: &mut Value, options: &FillOptions) {
+        match (self, node) {
+            (DataType::List(_), node) if node.is_null() && options.null_as_empty_array => *node = Value::Array(vec![]),
+            (DataType::Dict(_), node) if node.is_null() && options.null_as_empty_object => *node = Value::Object(Default::default()),
+            (DataType::Dict(inner), Value::Object(object)) => {
+                for (key, value) in object.iter_mut() {
+                    if let Some(field) = inner.fields.get(key) {
+                        field.coerce_nulls(value, options);
+                        continue;
+                    }
+                    let mut matched = false;
+                    for (pattern, field) in inner.any_fields.iter().flatten() {
+                        if pattern.is_match(key) {
+                            matched = true;
+                            field.coerce_nulls(value, options);
+                        }
+                    }
+                    if let Some(others) = inner.others.as_ref().filter(|_| !matched) {
+                        others.coerce_nulls(value, options);
+                    }
+                }
+            }
+            (DataType::List(inner), Value::Array(array)) => {
+                for item in array.iter_mut() {
+                    inner.element_type.coerce_nulls(item, options);
+                }
+            }
+            (DataType::Tuple(inner), Value::Array(array)) => {
+                for (index, item) in array.iter_mut().enumerate() {
+                    if let Some(element) = inner.element(index) {
+                        element.coerce_nulls(item, options);
+                    }
+                }
+            }
+            (DataType::OneOf(inner), node) => {
+                if let Some(variant) = inner.variants.iter().find(|variant| variant.validate_type(node)) {
+                    variant.coerce_nulls(node, options);
+                }
+            }
+            (DataType::Ref(inner), node) => {
+                if let Some(target) = inner.target() {
+                    target.coerce_nulls(node, options);
+                }
+            }
+            (DataType::Dynamic(inner), node) => {
+                if let Some(variant) = inner.select(node) {
+                    variant.coerce_nulls(node, options);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn apply_defaults(&self, node: &mut Value) {
+        match (self, node) {
+            (DataType::Dict(inner), Value::Object(object)) => {
+                for (key, field) in inner.fields.iter() {
+                    if !object.contains_key(key) && (inner.all_optional || field.is_optional()) {
+                        if let Some(default) = field.default_value() {
+                            object.insert(key.clone(), default.clone());
+                        }
+                    }
+                    if let Some(value) = object.get_mut(key) {
+                        field.apply_defaults(value);
+                    }
+                }
+            }
+            (DataType::List(inner), Value::Array(array)) => {
+                for item in array.iter_mut() {
+                    inner.element_type.apply_defaults(item);
+                }
+            }
+            (DataType::Tuple(inner), Value::Array(array)) => {
+                for (index, item) in array.iter_mut().enumerate() {
+                    if let Some(element) = inner.element(index) {
+                        element.apply_defaults(item);
+                    }
+                }
+            }
+            (DataType::OneOf(inner), node) => {
+                if let Some(variant) = inner.variants.iter().find(|variant| variant.validate_type(node)) {
+                    variant.apply_defaults(node);
+                }
+            }
+            (DataType::Ref(inner), node) => {
+                if let Some(target) = inner.target() {
+                    target.apply_defaults(node);
+                }
+            }
+            (DataType::Dynamic(inner), node) => {
+                if let Some(variant) = inner.select(node) {
+                    variant.apply_defaults(node);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn collect_absent(&self, node: &Value, path: &str, absent: &mut Vec<String>) {
+        match (self, node) {
+            (DataType::Dict(inner), Value::Object(object)) => {
+                for (key, field) in inner.fields.iter() {
+                    let field_path = format!("{}/{}", path, key.replace('~', "~0").replace('/', "~1"));
+                    match object.get(key) {
+                        Some(value) => field.collect_absent(value, &field_path, absent),
+                        None if inner.all_optional || field.is_optional() => absent.push(field_path),
+                        None => {}
+                    }
+                }
+            }
+            (DataType::List(inner), Value::Array(array)) => {
+                for (index, item) in array.iter().enumerate() {
+                    inner.element_type.collect_absent(item, &format!("{}/{}", path, index), absent);
+                }
+            }
+            (DataType::Tuple(inner), Value::Array(array)) => {
+                for (index, item) in array.iter().enumerate() {
+                    if let Some(element) = inner.element(index) {
+                        element.collect_absent(item, &format!("{}/{}", path, index), absent);
+                    }
+                }
+            }
+            (DataType::OneOf(inner), node) => {
+                if let Some(variant) = inner.variants.iter().find(|variant| variant.validate_type(node)) {
+                    variant.collect_absent(node, path, absent);
+                }
+            }
+            (DataType::Ref(inner), node) => {
+                if let Some(target) = inner.target() {
+                    target.collect_absent(node, path, absent);
+                }
+            }
+            (DataType::Dynamic(inner), node) => {
+                if let Some(variant) = inner.select(node) {
+                    variant.collect_absent(node, path, absent);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::fill::FillOptions;
+    use crate::schema::Schema;
+
+    #[test]
+    fn strip_unknown_should_remove_undeclared_keys_recursively() {
+        let schema: Schema = serde_json::from_value(json!({
+            "root": {
+                "type": "Dict",
+                "fields": {
+                    "name": { "type": "String" },
+                    "friends": {
+                        "type": "List",
+                        "element_type": { "type": "Dict", "fields": { "name": { "type": "String" } } }
+                    }
+                }
+            },
+            "validators": []
+        }))
+        .unwrap();
+        let document = json!({
+            "name": "kilerd",
+            "password": "secret",
+            "friends": [{ "name": "a", "age": 1 }, { "name": "b" }]
+        });
+
+        assert_eq!(
+            json!({ "name": "kilerd", "friends": [{ "name": "a" }, { "name": "b" }] }),
+            schema.strip_unknown(&document)
+        );
+    }
+
+    #[test]
+    fn fill_should_report_absent_optional_fields() {
+        let schema: Schema = serde_json::from_value(json!({
+            "root": {
+                "type": "Dict",
+                "fields": {
+                    "name": { "type": "String" },
+                    "nickname": { "type": "String", "optional": true },
+                    "address": {
+                        "type": "Dict",
+                        "fields": {
+                            "city": { "type": "String" },
+                            "zip": { "type": "String", "optional": true }
+                        }
+                    },
+                    "friends": {
+                        "type": "List",
+                        "element_type": {
+                            "type": "Dict",
+                            "fields": { "name": { "type": "String" }, "age": { "type": "Number", "optional": true } }
+                        }
+                    }
+                }
+            },
+            "validators": []
+        }))
+        .unwrap();
+        let document = json!({
+            "name": "kilerd",
+            "address": { "city": "Shenzhen" },
+            "friends": [{ "name": "a", "age": 1 }, { "name": "b" }]
+        });
+
+        let filled = schema.fill(&document);
+        assert_eq!(document, filled.value);
+        assert_eq!(vec!["/address/zip", "/friends/1/age", "/nickname"], filled.absent);
+    }
+
+    #[test]
+    fn apply_defaults_should_insert_absent_optional_fields() {
+        let schema: Schema = serde_json::from_value(json!({
+            "root": {
+                "type": "Dict",
+                "fields": {
+                    "host": { "type": "String", "optional": true, "default": "localhost" },
+                    "port": { "type": "Number", "optional": true, "default": 80 },
+                    "tls": {
+                        "type": "Dict",
+                        "optional": true,
+                        "default": {},
+                        "fields": { "verify": { "type": "Boolean", "optional": true, "default": true } }
+                    }
+                }
+            },
+            "validators": []
+        }))
+        .unwrap();
+        let mut document = json!({ "port": 8080 });
+        schema.apply_defaults(&mut document);
+
+        assert_eq!(json!({ "host": "localhost", "port": 8080, "tls": { "verify": true } }), document);
+    }
+
+    #[test]
+    fn fill_should_follow_refs_without_compiling() {
+        let schema: Schema = serde_json::from_value(json!({
+            "root": { "type": "Dict", "fields": { "home": { "type": "Ref", "name": "address" } } },
+            "definitions": {
+                "address": {
+                    "type": "Dict",
+                    "fields": {
+                        "city": { "type": "String" },
+                        "country": { "type": "String", "optional": true, "default": "CN" }
+                    }
+                }
+            },
+            "validators": []
+        }))
+        .unwrap();
+        let document = json!({ "home": { "city": "Shenzhen", "junk": 1 } });
+
+        assert_eq!(json!({ "home": { "city": "Shenzhen" } }), schema.strip_unknown(&document));
+        assert_eq!(vec!["/home/country"], schema.fill(&document).absent);
+        let mut document = document;
+        schema.apply_defaults(&mut document);
+        assert_eq!(json!({ "home": { "city": "Shenzhen", "junk": 1, "country": "CN" } }), document);
+    }
+
+    #[test]
+    fn fill_should_coerce_null_to_an_empty_array() {
+        let schema: Schema = serde_json::from_value(json!({
+            "root": {
+                "type": "Dict",
+                "fields": {
+                    "tags": { "type": "List", "element_type": { "type": "String" } },
+                    "groups": { "type": "List", "element_type": { "type": "List", "element_type": { "type": "String" } } }
+                }
+            },
+            "validators": []
+        }))
+        .unwrap();
+        let document = json!({ "tags": null, "groups": [["a"], null] });
+        assert!(!schema.validate(&schema.fill(&document).value));
+
+        let options = FillOptions { null_as_empty_array: true, ..Default::default() };
+        let filled = schema.fill_with(&document, &options);
+        assert_eq!(json!({ "tags": [], "groups": [["a"], []] }), filled.value);
+        assert!(schema.validate(&filled.value));
+    }
+
+    #[test]
+    fn fill_should_coerce_null_to_an_empty_object() {
+        let schema: Schema = serde_json::from_value(json!({
+            "root": {
+                "type": "Dict",
+                "fields": {
+                    "settings": { "type": "Dict", "fields": { "theme": { "type": "String", "optional": true } } },
+                    "owner": { "type": "Dict", "fields": { "name": { "type": "String" } } },
+                    "tags": { "type": "List", "nullable": true, "element_type": { "type": "String" } }
+                }
+            },
+            "validators": []
+        }))
+        .unwrap();
+        let options = FillOptions { null_as_empty_object: true, ..Default::default() };
+        let filled = schema.fill_with(&json!({ "settings": null, "owner": { "name": "kilerd" }, "tags": null }), &options);
+        assert_eq!(json!({ "settings": {}, "owner": { "name": "kilerd" }, "tags": null }), filled.value);
+        assert_eq!(vec!["/settings/theme"], filled.absent);
+        assert!(schema.validate(&filled.value));
+
+        // the coerced object is validated like any other
+        let filled = schema.fill_with(&json!({ "settings": {}, "owner": null, "tags": [] }), &options);
+        let errors = schema.validate_detailed(&filled.value).unwrap_err();
+        assert_eq!("/owner/name", errors[0].path);
+    }
+}
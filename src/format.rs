@@ -0,0 +1,690 @@
+//! Built-in string formats and the registry for custom ones.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const DATE_TIME: &str = "date-time";
+pub const DECIMAL: &str = "decimal";
+pub const HEX: &str = "hex";
+pub const BINARY: &str = "binary";
+pub const UUID: &str = "uuid";
+pub const JSON_POINTER: &str = "json-pointer";
+pub const JSONPATH: &str = "jsonpath";
+
+const BUILTIN_FORMATS: &[&str] = &[DATE_TIME, DECIMAL, HEX, BINARY, UUID, JSON_POINTER, JSONPATH];
+
+pub type FormatCheck = dyn Fn(&str) -> bool + Send + Sync;
+
+/// Custom string formats, consulted before the built-in ones.
+#[derive(Clone, Default)]
+pub struct FormatRegistry {
+    formats: HashMap<String, Arc<FormatCheck>>,
+}
+
+impl FormatRegistry {
+    pub fn new() -> Self {
+        FormatRegistry::default()
+    }
+
+    pub fn register(&mut self, name: impl Into<String>, check: impl Fn(&str) -> bool + Send + Sync + 'static) -> &mut Self {
+        self.formats.insert(name.into(), Arc::new(check));
+        self
+    }
+
+    pub fn get(&self, name: &str) -> Option<&FormatCheck> {
+        self.formats.get(name).map(|check| check.as_ref())
+    }
+
+    /// whether the format is either registered or built in
+    pub fn contains(&self, name: &str) -> bool {
+        self.formats.contains_key(name) || BUILTIN_FORMATS.contains(&name)
+    }
+}
+
+impl fmt::Debug for FormatRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.formats.keys()).finish()
+    }
+}
+
+/// A parsed RFC 3339 date-time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct DateTime {
+    /// seconds since the unix epoch, a date-time without offset is read as UTC
+    pub timestamp: i64,
+    /// offset from UTC in seconds, `None` when the input carries no offset
+    pub offset: Option<i32>,
+}
+
+fn digits(input: &str) -> Option<u32> {
+    if input.is_empty() || !input.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    input.parse().ok()
+}
+
+fn is_leap_year(year: i64) -> bool {
+    (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+}
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        4 | 6 | 9 | 11 => 30,
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        _ => 31,
+    }
+}
+
+/// days since the unix epoch of a proleptic gregorian date
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = if year >= 0 { year } else { year - 399 } / 400;
+    let year_of_era = year - era * 400;
+    let month = month as i64;
+    let day_of_year = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// seconds since the unix epoch of a civil date and time
+fn timestamp(year: i64, month: u32, day: u32, hour: u32, minute: u32, second: u32) -> i64 {
+    days_from_civil(year, month, day) * 86_400 + (hour * 3_600 + minute * 60 + second) as i64
+}
+
+fn is_valid_date(year: i64, month: u32, day: u32) -> bool {
+    (1..=12).contains(&month) && day >= 1 && day <= days_in_month(year, month)
+}
+
+fn is_valid_time(hour: u32, minute: u32, second: u32) -> bool {
+    // 60 is a leap second
+    hour < 24 && minute < 60 && second <= 60
+}
+
+fn parse_offset(input: &str) -> Option<Option<i32>> {
+    match input {
+        "" => Some(None),
+        "Z" | "z" => Some(Some(0)),
+        _ => {
+            let sign = match &input[..1] {
+                "+" => 1,
+                "-" => -1,
+                _ => return None,
+            };
+            let (hour, minute) = input[1..].split_once(':')?;
+            if hour.len() != 2 || minute.len() != 2 {
+                return None;
+            }
+            let (hour, minute) = (digits(hour)?, digits(minute)?);
+            if hour >= 24 || minute >= 60 {
+                return None;
+            }
+            Some(Some(sign * (hour * 3_600 + minute * 60) as i32))
+        }
+    }
+}
+
+/// Parse `YYYY-MM-DDTHH:MM:SS[.fraction][Z|±HH:MM]`.
+pub(crate) fn parse_date_time(input: &str) -> Option<DateTime> {
+    if input.len() < 19 || !input.is_ascii() {
+        return None;
+    }
+    let (date, rest) = input.split_at(10);
+    let (separator, rest) = rest.split_at(1);
+    if !matches!(separator, "T" | "t" | " ") {
+        return None;
+    }
+    let (time, rest) = rest.split_at(8);
+
+    let date: Vec<&str> = date.split('-').collect();
+    let time: Vec<&str> = time.split(':').collect();
+    if date.len() != 3 || date[0].len() != 4 || date[1].len() != 2 || date[2].len() != 2 {
+        return None;
+    }
+    if time.len() != 3 || time.iter().any(|part| part.len() != 2) {
+        return None;
+    }
+    let (year, month, day) = (digits(date[0])? as i64, digits(date[1])?, digits(date[2])?);
+    let (hour, minute, second) = (digits(time[0])?, digits(time[1])?, digits(time[2])?);
+    if !is_valid_date(year, month, day) || !is_valid_time(hour, minute, second) {
+        return None;
+    }
+
+    let rest = match rest.strip_prefix('.') {
+        Some(fraction) => {
+            let end = fraction.find(|c: char| !c.is_ascii_digit()).unwrap_or(fraction.len());
+            if end == 0 {
+                return None;
+            }
+            &fraction[end..]
+        }
+        None => rest,
+    };
+    let offset = parse_offset(rest)?;
+
+    let local = timestamp(year, month, day, hour, minute, second);
+    Some(DateTime {
+        timestamp: local - offset.unwrap_or(0) as i64,
+        offset,
+    })
+}
+
+pub(crate) fn unix_timestamp(time: SystemTime) -> i64 {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(duration) => duration.as_secs() as i64,
+        Err(err) => -(err.duration().as_secs() as i64),
+    }
+}
+
+/// Resolve a time bound, either an absolute date-time or `now` optionally shifted
+/// by a number of seconds, minutes, hours, days or weeks like `now+7d` or `now-30d`.
+pub(crate) fn parse_time_bound(bound: &str, now: i64) -> Option<i64> {
+    let relative = match bound.strip_prefix("now") {
+        Some(relative) => relative,
+        None => return parse_date_time(bound).map(|date_time| date_time.timestamp),
+    };
+    if relative.is_empty() {
+        return Some(now);
+    }
+    if relative.len() < 3 || !relative.is_ascii() {
+        return None;
+    }
+    let sign = match &relative[..1] {
+        "+" => 1,
+        "-" => -1,
+        _ => return None,
+    };
+    let (amount, unit) = relative[1..].split_at(relative.len() - 2);
+    let unit = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 3_600,
+        "d" => 86_400,
+        "w" => 604_800,
+        _ => return None,
+    };
+    let amount = digits(amount)? as i64;
+    Some(now + sign * amount * unit)
+}
+
+fn is_digits(input: &str) -> bool {
+    !input.is_empty() && input.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// Parse a localized decimal like `1,234.56` or `1.234,56`.
+///
+/// With a grouping separator the integer part is either plain digits or groups of three
+/// digits after a leading group of one to three.
+pub(crate) fn parse_decimal(input: &str, grouping: Option<char>, decimal_separator: char) -> Option<f64> {
+    let unsigned = input.strip_prefix('-').unwrap_or(input);
+    let (integer, fraction) = match unsigned.split_once(decimal_separator) {
+        Some((integer, fraction)) => (integer, Some(fraction)),
+        None => (unsigned, None),
+    };
+    if let Some(fraction) = fraction {
+        if !is_digits(fraction) {
+            return None;
+        }
+    }
+
+    let digits = match grouping.filter(|separator| integer.contains(*separator)) {
+        Some(separator) => {
+            let groups: Vec<&str> = integer.split(separator).collect();
+            let leading = groups[0];
+            if !is_digits(leading) || leading.len() > 3 || groups[1..].iter().any(|group| group.len() != 3 || !is_digits(group)) {
+                return None;
+            }
+            groups.concat()
+        }
+        None if is_digits(integer) => integer.to_owned(),
+        None => return None,
+    };
+
+    let negative = if unsigned.len() != input.len() { "-" } else { "" };
+    let canonical = match fraction {
+        Some(fraction) => format!("{}{}.{}", negative, digits, fraction),
+        None => format!("{}{}", negative, digits),
+    };
+    canonical.parse().ok()
+}
+
+/// The number of significant bits of a hex (radix 16) or binary (radix 2) string,
+/// an optional `0x` or `0b` prefix is accepted.
+pub(crate) fn significant_bits(input: &str, radix: u32) -> Option<u32> {
+    let prefix = if radix == 16 { "0x" } else { "0b" };
+    let digits = input.strip_prefix(prefix).unwrap_or(input);
+    if digits.is_empty() || !digits.chars().all(|c| c.is_digit(radix)) {
+        return None;
+    }
+    let significant = digits.trim_start_matches('0');
+    let leading = match significant.chars().next() {
+        Some(leading) => leading.to_digit(radix)?,
+        None => return Some(0),
+    };
+    let bits_per_digit = radix.trailing_zeros();
+    let rest = (significant.len() as u32 - 1) * bits_per_digit;
+    Some(rest + (u32::BITS - leading.leading_zeros()))
+}
+
+/// The version nibble of a hyphenated UUID like `123e4567-e89b-42d3-a456-426614174000`, in either case.
+pub(crate) fn uuid_version(input: &str) -> Option<u8> {
+    let groups: Vec<&str> = input.split('-').collect();
+    let lengths = groups.iter().map(|group| group.len());
+    if !lengths.eq([8, 4, 4, 4, 12].iter().copied()) || !groups.iter().all(|group| is_hex(group)) {
+        return None;
+    }
+    groups[2].chars().next()?.to_digit(16).map(|version| version as u8)
+}
+
+fn is_hex(input: &str) -> bool {
+    input.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// RFC 6901: empty, or `/`-prefixed tokens in which every `~` is escaped as `~0` or `~1`
+pub(crate) fn is_json_pointer(input: &str) -> bool {
+    input.is_empty()
+        || input.starts_with('/') && input.split('~').skip(1).all(|after| after.starts_with(['0', '1']))
+}
+
+/// The syntax of an RFC 9535 path like `$.store.book[?@.price < 10].title`,
+/// filter expressions are only checked for balanced brackets and quotes.
+pub(crate) fn is_jsonpath(input: &str) -> bool {
+    let mut rest = match input.strip_prefix('$') {
+        Some(rest) => rest,
+        None => return false,
+    };
+    while !rest.is_empty() {
+        rest = match take_segment(rest) {
+            Some(rest) => rest,
+            None => return false,
+        };
+    }
+    true
+}
+
+fn take_segment(input: &str) -> Option<&str> {
+    if let Some(rest) = input.strip_prefix("..") {
+        return if rest.starts_with('[') { take_bracket(rest) } else { take_member(rest) };
+    }
+    if let Some(rest) = input.strip_prefix('.') {
+        return take_member(rest);
+    }
+    take_bracket(input)
+}
+
+/// `*` or a name like `book`, which may not start with a digit
+fn take_member(input: &str) -> Option<&str> {
+    if let Some(rest) = input.strip_prefix('*') {
+        return Some(rest);
+    }
+    let is_name = |(index, c): &(usize, char)| {
+        *c == '_' || c.is_ascii_alphabetic() || !c.is_ascii() || (*index > 0 && c.is_ascii_digit())
+    };
+    let end = input.char_indices().find(|item| !is_name(item)).map_or(input.len(), |(index, _)| index);
+    if end == 0 { None } else { Some(&input[end..]) }
+}
+
+fn take_bracket(input: &str) -> Option<&str> {
+    let mut rest = input.strip_prefix('[')?;
+    loop {
+        rest = take_selector(rest.trim_start())?.trim_start();
+        match rest.strip_prefix(',') {
+            Some(next) => rest = next,
+            None => return rest.strip_prefix(']'),
+        }
+    }
+}
+
+fn take_selector(input: &str) -> Option<&str> {
+    match input.chars().next()? {
+        '\'' | '"' => take_quoted(input),
+        '*' => Some(&input[1..]),
+        '?' => take_filter(&input[1..]),
+        _ => take_index_or_slice(input),
+    }
+}
+
+/// a string in single or double quotes, with backslash escapes
+fn take_quoted(input: &str) -> Option<&str> {
+    let quote = input.chars().next()?;
+    let mut chars = input.char_indices().skip(1);
+    while let Some((index, c)) = chars.next() {
+        if c == '\\' {
+            chars.next()?;
+        } else if c == quote {
+            return Some(&input[index + 1..]);
+        }
+    }
+    None
+}
+
+fn take_integer(input: &str) -> Option<&str> {
+    let start = if input.starts_with('-') { 1 } else { 0 };
+    let end = input[start..].find(|c: char| !c.is_ascii_digit()).map_or(input.len(), |end| start + end);
+    if end == start { None } else { Some(&input[end..]) }
+}
+
+/// an index like `-1`, or a slice of up to three optional integers like `1:` or `::2`
+fn take_index_or_slice(input: &str) -> Option<&str> {
+    let mut rest = take_integer(input).unwrap_or(input);
+    let has_start = rest.len() != input.len();
+    let mut colons = 0;
+    while colons < 2 {
+        match rest.trim_start().strip_prefix(':') {
+            Some(next) => {
+                colons += 1;
+                let next = next.trim_start();
+                rest = take_integer(next).unwrap_or(next);
+            }
+            None => break,
+        }
+    }
+    if has_start || colons > 0 { Some(rest) } else { None }
+}
+
+/// everything up to the `]` or `,` closing the selector, which has to be non-empty
+fn take_filter(input: &str) -> Option<&str> {
+    let bytes = input.as_bytes();
+    let mut depth = 0;
+    let mut index = 0;
+    while index < bytes.len() {
+        match bytes[index] {
+            b'\'' | b'"' => {
+                index = input.len() - take_quoted(&input[index..])?.len();
+                continue;
+            }
+            b'(' | b'[' => depth += 1,
+            b')' | b']' if depth > 0 => depth -= 1,
+            b']' | b',' if depth == 0 => return if input[..index].trim().is_empty() { None } else { Some(&input[index..]) },
+            b')' => return None,
+            _ => {}
+        }
+        index += 1;
+    }
+    None
+}
+
+const SPECIFIERS: &str = "YyjmdHIMSfbhBaApzFT%";
+
+const MONTHS: [&str; 12] = [
+    "january", "february", "march", "april", "may", "june",
+    "july", "august", "september", "october", "november", "december",
+];
+
+const WEEKDAYS: [&str; 7] = ["monday", "tuesday", "wednesday", "thursday", "friday", "saturday", "sunday"];
+
+/// whether every `%` in the pattern starts a specifier [`matches_strftime`] understands
+pub(crate) fn is_valid_strftime(pattern: &str) -> bool {
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        if c == '%' && !chars.next().is_some_and(|spec| SPECIFIERS.contains(spec)) {
+            return false;
+        }
+    }
+    true
+}
+
+#[derive(Default)]
+struct DateFields {
+    year: Option<i64>,
+    month: Option<u32>,
+    day: Option<u32>,
+    day_of_year: Option<u32>,
+}
+
+fn take_digits(input: &mut &str, count: usize) -> Option<u32> {
+    if input.len() < count || !input.as_bytes()[..count].iter().all(u8::is_ascii_digit) {
+        return None;
+    }
+    let (head, rest) = input.split_at(count);
+    *input = rest;
+    head.parse().ok()
+}
+
+fn in_range(value: u32, low: u32, high: u32) -> Option<u32> {
+    (low..=high).contains(&value).then_some(value)
+}
+
+/// the index of the name the input starts with, ignoring case
+fn take_name(input: &mut &str, names: &[&str], abbreviated: bool) -> Option<usize> {
+    names.iter().position(|name| {
+        let name = if abbreviated { &name[..3] } else { name };
+        let matched = input.get(..name.len()).is_some_and(|head| head.eq_ignore_ascii_case(name));
+        if matched {
+            *input = &input[name.len()..];
+        }
+        matched
+    })
+}
+
+fn scan(input: &mut &str, pattern: &str, fields: &mut DateFields) -> Option<()> {
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            *input = input.strip_prefix(c)?;
+            continue;
+        }
+        match chars.next()? {
+            'Y' => fields.year = Some(take_digits(input, 4)? as i64),
+            'y' => {
+                let year = take_digits(input, 2)? as i64;
+                fields.year = Some(if year < 69 { 2000 + year } else { 1900 + year });
+            }
+            'j' => fields.day_of_year = Some(in_range(take_digits(input, 3)?, 1, 366)?),
+            'm' => fields.month = Some(in_range(take_digits(input, 2)?, 1, 12)?),
+            'd' => fields.day = Some(in_range(take_digits(input, 2)?, 1, 31)?),
+            'H' => {
+                in_range(take_digits(input, 2)?, 0, 23)?;
+            }
+            'I' => {
+                in_range(take_digits(input, 2)?, 1, 12)?;
+            }
+            'M' => {
+                in_range(take_digits(input, 2)?, 0, 59)?;
+            }
+            'S' => {
+                in_range(take_digits(input, 2)?, 0, 60)?;
+            }
+            'f' => {
+                let count = input.bytes().take_while(u8::is_ascii_digit).count();
+                if !(1..=9).contains(&count) {
+                    return None;
+                }
+                *input = &input[count..];
+            }
+            'b' | 'h' => fields.month = Some(take_name(input, &MONTHS, true)? as u32 + 1),
+            'B' => fields.month = Some(take_name(input, &MONTHS, false)? as u32 + 1),
+            'a' => {
+                take_name(input, &WEEKDAYS, true)?;
+            }
+            'A' => {
+                take_name(input, &WEEKDAYS, false)?;
+            }
+            'p' => {
+                take_name(input, &["am", "pm"], false)?;
+            }
+            'z' => {
+                *input = input.strip_prefix(['+', '-'])?;
+                in_range(take_digits(input, 2)?, 0, 23)?;
+                *input = input.strip_prefix(':').unwrap_or(input);
+                in_range(take_digits(input, 2)?, 0, 59)?;
+            }
+            'F' => scan(input, "%Y-%m-%d", fields)?,
+            'T' => scan(input, "%H:%M:%S", fields)?,
+            '%' => *input = input.strip_prefix('%')?,
+            _ => return None,
+        }
+    }
+    Some(())
+}
+
+/// Whether the input parses under a strftime pattern like `%Y-%m-%d %H:%M:%S`,
+/// including that the day exists in its month.
+pub(crate) fn matches_strftime(input: &str, pattern: &str) -> bool {
+    let mut rest = input;
+    let mut fields = DateFields::default();
+    if scan(&mut rest, pattern, &mut fields).is_none() || !rest.is_empty() {
+        return false;
+    }
+    // without a year, assume a leap one so that `%m-%d` still accepts `02-29`
+    let year = fields.year.unwrap_or(2000);
+    if let (Some(month), Some(day)) = (fields.month, fields.day) {
+        if !is_valid_date(year, month, day) {
+            return false;
+        }
+    }
+    fields.day_of_year != Some(366) || is_leap_year(year)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::format::{
+        is_valid_strftime, matches_strftime, parse_date_time, parse_decimal, parse_time_bound, significant_bits, uuid_version,
+        is_json_pointer, is_jsonpath, DateTime,
+    };
+
+    #[test]
+    fn should_parse_date_time() {
+        assert_eq!(
+            Some(DateTime { timestamp: 0, offset: Some(0) }),
+            parse_date_time("1970-01-01T00:00:00Z")
+        );
+        assert_eq!(
+            Some(DateTime { timestamp: 1_000_000_000, offset: Some(0) }),
+            parse_date_time("2001-09-09T01:46:40.123Z")
+        );
+        assert_eq!(
+            Some(DateTime { timestamp: 1_000_000_000, offset: Some(7_200) }),
+            parse_date_time("2001-09-09T03:46:40+02:00")
+        );
+        assert_eq!(
+            Some(DateTime { timestamp: 951_782_400, offset: None }),
+            parse_date_time("2000-02-29 00:00:00")
+        );
+    }
+
+    #[test]
+    fn should_reject_malformed_date_time() {
+        assert_eq!(None, parse_date_time("2001-09-09"));
+        assert_eq!(None, parse_date_time("2001-02-29T00:00:00Z"));
+        assert_eq!(None, parse_date_time("2001-13-01T00:00:00Z"));
+        assert_eq!(None, parse_date_time("2001-01-01T24:00:00Z"));
+        assert_eq!(None, parse_date_time("2001-01-01T00:00:00."));
+        assert_eq!(None, parse_date_time("2001-01-01T00:00:00+0200"));
+        assert_eq!(None, parse_date_time("2001-01-01X00:00:00Z"));
+    }
+
+    #[test]
+    fn should_resolve_time_bound() {
+        assert_eq!(Some(100), parse_time_bound("now", 100));
+        assert_eq!(Some(100 + 7 * 86_400), parse_time_bound("now+7d", 100));
+        assert_eq!(Some(100 - 30), parse_time_bound("now-30s", 100));
+        assert_eq!(Some(0), parse_time_bound("1970-01-01T00:00:00Z", 100));
+        assert_eq!(None, parse_time_bound("now+7y", 100));
+        assert_eq!(None, parse_time_bound("now+", 100));
+        assert_eq!(None, parse_time_bound("tomorrow", 100));
+    }
+
+    #[test]
+    fn should_parse_us_decimal() {
+        assert_eq!(Some(1234.56), parse_decimal("1,234.56", Some(','), '.'));
+        assert_eq!(Some(1234.56), parse_decimal("1234.56", Some(','), '.'));
+        assert_eq!(Some(-1_234_567.0), parse_decimal("-1,234,567", Some(','), '.'));
+        assert_eq!(Some(0.5), parse_decimal("0.5", None, '.'));
+        assert_eq!(None, parse_decimal("1,234.56", None, '.'));
+        assert_eq!(None, parse_decimal("12,34.56", Some(','), '.'));
+        assert_eq!(None, parse_decimal("1234,567", Some(','), '.'));
+        assert_eq!(None, parse_decimal(",234", Some(','), '.'));
+        assert_eq!(None, parse_decimal("1.", Some(','), '.'));
+        assert_eq!(None, parse_decimal("", Some(','), '.'));
+    }
+
+    #[test]
+    fn should_parse_european_decimal() {
+        assert_eq!(Some(1234.56), parse_decimal("1.234,56", Some('.'), ','));
+        assert_eq!(Some(1_234_567.0), parse_decimal("1 234 567", Some(' '), ','));
+        assert_eq!(None, parse_decimal("1,234.56", Some('.'), ','));
+        assert_eq!(None, parse_decimal("1.23,4", Some('.'), ','));
+    }
+
+    #[test]
+    fn should_count_significant_bits() {
+        assert_eq!(Some(8), significant_bits("ff", 16));
+        assert_eq!(Some(8), significant_bits("0x00FF", 16));
+        assert_eq!(Some(9), significant_bits("1ff", 16));
+        assert_eq!(Some(0), significant_bits("000", 16));
+        assert_eq!(Some(3), significant_bits("0b0101", 2));
+        assert_eq!(None, significant_bits("0x", 16));
+        assert_eq!(None, significant_bits("fg", 16));
+        assert_eq!(None, significant_bits("102", 2));
+    }
+
+    #[test]
+    fn should_read_uuid_version() {
+        assert_eq!(Some(4), uuid_version("123e4567-e89b-42d3-a456-426614174000"));
+        assert_eq!(Some(7), uuid_version("01890A5D-AC96-774B-BCCE-B302099A8057"));
+        assert_eq!(Some(1), uuid_version("c232ab00-9414-11ec-b3c8-9f6bdeced846"));
+        assert_eq!(None, uuid_version("123e4567e89b42d3a456426614174000"));
+        assert_eq!(None, uuid_version("123e4567-e89b-42d3-a456-42661417400g"));
+        assert_eq!(None, uuid_version(""));
+    }
+
+    #[test]
+    fn should_check_json_pointer_syntax() {
+        for pointer in ["", "/", "/users/0/email", "/a~1b", "/m~0n", "/~01"] {
+            assert!(is_json_pointer(pointer), "{} rejected", pointer);
+        }
+        for pointer in ["users/0", "/a~2b", "/a~", "#/users"] {
+            assert!(!is_json_pointer(pointer), "{} accepted", pointer);
+        }
+    }
+
+    #[test]
+    fn should_check_jsonpath_syntax() {
+        let valid = vec![
+            "$",
+            "$.store.book[0].title",
+            "$..author",
+            "$['store'][\"book\"][*]",
+            "$.book[-1:]",
+            "$.book[::2]",
+            "$.book[0, 1]",
+            "$.book[?@.price < 10]",
+            "$.book[?(@.isbn && match(@.title, '[a-z]+'))]",
+            "$['it\\'s']",
+            "$.名前",
+        ];
+        for path in valid {
+            assert!(is_jsonpath(path), "{} rejected", path);
+        }
+        let malformed = vec!["store.book", "$.", "$..", "$[", "$[0", "$.1st", "$[:::]", "$[?]", "$['open]", "$.a)"];
+        for path in malformed {
+            assert!(!is_jsonpath(path), "{} accepted", path);
+        }
+    }
+
+    #[test]
+    fn should_match_strftime_pattern() {
+        assert!(matches_strftime("2024-02-29 23:59:60", "%Y-%m-%d %H:%M:%S"));
+        assert!(matches_strftime("2024-02-29T23:59:59.123+0800", "%FT%T.%f%z"));
+        assert!(matches_strftime("Thu, 07 Mar 2024 09:15:00 PM", "%a, %d %b %Y %I:%M:%S %p"));
+        assert!(matches_strftime("[07/March/24] 100%", "[%d/%B/%y] 100%%"));
+        assert!(matches_strftime("2024 366", "%Y %j"));
+
+        assert!(!matches_strftime("2023-02-29 00:00:00", "%Y-%m-%d %H:%M:%S"));
+        assert!(!matches_strftime("2024-02-28 24:00:00", "%Y-%m-%d %H:%M:%S"));
+        assert!(!matches_strftime("2024-2-28 00:00:00", "%Y-%m-%d %H:%M:%S"));
+        assert!(!matches_strftime("2024-02-28 00:00:00 ", "%Y-%m-%d %H:%M:%S"));
+        assert!(!matches_strftime("2023 366", "%Y %j"));
+        assert!(!matches_strftime("07 Mars 2024", "%d %b %Y"));
+    }
+
+    #[test]
+    fn should_reject_unknown_strftime_specifier() {
+        assert!(is_valid_strftime("%Y-%m-%d %% %FT%T"));
+        assert!(!is_valid_strftime("%Y-%Q"));
+        assert!(!is_valid_strftime("%Y-%"));
+    }
+}
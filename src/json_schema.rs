@@ -0,0 +1,519 @@
+//! Exporting schemas as JSON Schema (draft-07), and importing a subset of it.
+//!
+//! Constraints without a draft-07 keyword are left out of the export, so the exported schema
+//! accepts everything the original does and possibly more:
+//!
+//! - dict `integer_key_sequence`, `unique_values`, `shapes`, `length_of`, `graph_integrity` and `parallel_arrays`
+//! - list `exact_set`, `element_relation`, `strictly_sorted_by`, `unique_ignoring` and `probability_distribution`
+//! - string lengths counted in bytes or graphemes, `after`/`before`, `column`, `strftime`,
+//!   `no_surrounding_whitespace` and every format but `date-time` and `json-pointer`
+//! - number `safe_integer`, `fits_f32`, `format` and a `grid` with an offset
+//! - `features`, as every constraint is exported as if its features were enabled
+//! - dynamic types, which export as `{}`
+
+use std::collections::HashMap;
+use std::fmt;
+
+use serde_json::{json, Map, Value};
+
+use crate::format;
+use crate::schema::{
+    BooleanType, DataType, DictType, LengthUnit, ListType, LiteralType, NullType, NumberType, RefType, Schema, StringType,
+};
+use crate::validator::Path;
+
+const DRAFT_07: &str = "http://json-schema.org/draft-07/schema#";
+
+impl Schema {
+    pub fn to_json_schema(&self) -> Value {
+        let mut exported = match export(&self.root) {
+            Value::Object(object) => object,
+            _ => unreachable!(),
+        };
+        exported.insert("$schema".to_owned(), json!(DRAFT_07));
+        if !self.definitions.is_empty() {
+            let definitions = self.definitions.iter().map(|(name, definition)| (name.clone(), export(definition)));
+            exported.insert("definitions".to_owned(), Value::Object(definitions.collect()));
+        }
+        Value::Object(exported)
+    }
+
+    /// Build a schema from JSON Schema using `type`, `properties`, `required`, `additionalProperties`,
+    /// `items`, `enum`, `pattern`, `maxLength`, `maxItems` and `default`, plus top-level `definitions`
+    /// and `$ref`s to them like `#/definitions/address`.
+    ///
+    /// Any other keyword fails the import instead of being dropped, except for pure annotations
+    /// like `title` and `description`. Objects have to set `additionalProperties`, because say
+    /// rejects undeclared fields unless told what type they have.
+    pub fn from_json_schema(value: &Value) -> Result<Schema, ImportError> {
+        let mut value = value.clone();
+        let definitions = match value.as_object_mut().and_then(|object| object.remove("definitions")) {
+            Some(Value::Object(definitions)) => definitions,
+            Some(_) => return Err(invalid(&Path::Root, "`definitions` must be an object")),
+            None => Map::new(),
+        };
+        let mut schema = import(&value, &Path::Root).map(Schema::new)?;
+        let definitions_path = Path::Root.key("definitions");
+        for (name, definition) in definitions.iter() {
+            schema.definitions.insert(name.clone(), import(definition, &definitions_path.key(name))?);
+        }
+        Ok(schema)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImportError {
+    /// the keyword has no say equivalent, at least not for the type it appears on
+    Unsupported { path: String, keyword: String },
+    Invalid { path: String, reason: String },
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (path, message) = match self {
+            ImportError::Unsupported { path, keyword } => (path, format!("unsupported keyword `{}`", keyword)),
+            ImportError::Invalid { path, reason } => (path, reason.clone()),
+        };
+        let path = if path.is_empty() { "root" } else { path };
+        write!(f, "{}: {}", path, message)
+    }
+}
+
+impl std::error::Error for ImportError {}
+
+const ANNOTATIONS: &[&str] = &["$schema", "$id", "$comment", "title", "description", "examples"];
+
+fn invalid(path: &Path, reason: impl Into<String>) -> ImportError {
+    ImportError::Invalid { path: path.to_string(), reason: reason.into() }
+}
+
+fn import(value: &Value, path: &Path) -> Result<DataType, ImportError> {
+    let mut keywords = match value {
+        Value::Object(object) => object.clone(),
+        _ => return Err(invalid(path, "expected a schema object")),
+    };
+    for annotation in ANNOTATIONS {
+        keywords.remove(*annotation);
+    }
+    if let Some(reference) = keywords.remove("$ref") {
+        let name = reference.as_str().and_then(|reference| reference.strip_prefix("#/definitions/"));
+        let name = name.ok_or_else(|| invalid(path, "only `$ref`s to `#/definitions/` are supported"))?;
+        if let Some(keyword) = keywords.keys().next() {
+            return Err(ImportError::Unsupported { path: path.to_string(), keyword: keyword.clone() });
+        }
+        return Ok(DataType::Ref(Box::new(RefType::new(name))));
+    }
+    let default = keywords.remove("default");
+
+    let (name, nullable) = match keywords.remove("type") {
+        Some(Value::String(name)) => (name, false),
+        Some(Value::Array(names)) => {
+            let mut names: Vec<&str> = names.iter().filter_map(Value::as_str).collect();
+            let nullable = names.contains(&"null");
+            names.retain(|name| *name != "null");
+            match names.as_slice() {
+                [name] => (name.to_string(), nullable),
+                [] => ("null".to_owned(), false),
+                _ => return Err(invalid(path, "a `type` with several non-null types")),
+            }
+        }
+        // an `enum` of strings is enough to tell a literal
+        None if keywords.get("enum").and_then(Value::as_array).is_some_and(|candidates| {
+            candidates.iter().all(|candidate| candidate.is_string() || candidate.is_null())
+        }) => ("string".to_owned(), false),
+        _ => return Err(invalid(path, "expected a `type`")),
+    };
+    let candidates = keywords.remove("enum").map(|candidates| match candidates {
+        Value::Array(candidates) => Ok(candidates),
+        _ => Err(invalid(path, "`enum` must be an array")),
+    });
+    let candidates = candidates.transpose()?;
+    // a `null` candidate is the same as a nullable type
+    let nullable = nullable || candidates.iter().flatten().any(Value::is_null);
+    let candidates: Option<Vec<Value>> =
+        candidates.map(|candidates| candidates.into_iter().filter(|candidate| !candidate.is_null()).collect());
+
+    let data_type = match name.as_str() {
+        "object" => {
+            let properties = match keywords.remove("properties") {
+                Some(Value::Object(properties)) => properties,
+                Some(_) => return Err(invalid(path, "`properties` must be an object")),
+                None => Map::new(),
+            };
+            let required: Vec<String> = match keywords.remove("required") {
+                Some(Value::Array(required)) => {
+                    required.iter().filter_map(Value::as_str).map(str::to_owned).collect()
+                }
+                Some(_) => return Err(invalid(path, "`required` must be an array")),
+                None => vec![],
+            };
+            let others = match keywords.remove("additionalProperties") {
+                Some(Value::Bool(false)) => None,
+                Some(others @ Value::Object(_)) => Some(import(&others, &path.key("additionalProperties"))?),
+                _ => return Err(invalid(path, "expected `additionalProperties` to be `false` or a schema")),
+            };
+            let properties_path = path.key("properties");
+            let mut fields = HashMap::new();
+            for (key, property) in properties.iter() {
+                let mut field = import(property, &properties_path.key(key))?;
+                set_optional(&mut field, !required.contains(key));
+                fields.insert(key.clone(), field);
+            }
+            if let Some(missing) = required.iter().find(|key| !fields.contains_key(*key)) {
+                return Err(invalid(path, format!("required field `{}` has no property", missing)));
+            }
+            DataType::Dict(Box::new(DictType { nullable, default, fields, others, ..Default::default() }))
+        }
+        "array" => {
+            let items = match keywords.remove("items") {
+                Some(items @ Value::Object(_)) => items,
+                _ => return Err(invalid(path, "expected `items` to be a single schema")),
+            };
+            let element_type = import(&items, &path.key("items"))?;
+            let max_items = keywords.remove("maxItems").map(|max| {
+                max.as_u64().ok_or_else(|| invalid(path, "`maxItems` must be a non-negative integer"))
+            });
+            DataType::List(Box::new(ListType {
+                optional: false,
+                nullable,
+                features: vec![],
+                default,
+                description: None,
+                element_type,
+                max_items: max_items.transpose()?,
+                exact_set: None,
+                element_relation: None,
+                strictly_sorted_by: None,
+                unique_ignoring: None,
+                probability_distribution: None,
+            }))
+        }
+        "string" if candidates.is_some() => {
+            let candidate = candidates.into_iter().flatten().map(|candidate| match candidate {
+                Value::String(candidate) => Ok(candidate),
+                _ => Err(invalid(path, "a string `enum` with a non-string candidate")),
+            });
+            let candidate = candidate.collect::<Result<_, _>>()?;
+            DataType::Literal(Box::new(LiteralType { nullable, default, candidate, ..Default::default() }))
+        }
+        "string" => {
+            let length = keywords.remove("maxLength").map(|max| {
+                max.as_u64().ok_or_else(|| invalid(path, "`maxLength` must be a non-negative integer"))
+            });
+            let regex = match keywords.remove("pattern") {
+                Some(Value::String(pattern)) => Some(unanchored(&pattern).into()),
+                Some(_) => return Err(invalid(path, "`pattern` must be a string")),
+                None => None,
+            };
+            let length = length.transpose()?;
+            DataType::String(Box::new(StringType { nullable, default, length, regex, ..Default::default() }))
+        }
+        "number" | "integer" => {
+            let candidate = candidates.map(|candidates| {
+                let as_number = |candidate: &Value| {
+                    candidate.as_f64().ok_or_else(|| invalid(path, "a numeric `enum` with a non-number candidate"))
+                };
+                candidates.iter().map(as_number).collect::<Result<Vec<f64>, _>>()
+            });
+            let candidate = candidate.transpose()?;
+            let integer = name == "integer";
+            DataType::Number(Box::new(NumberType { nullable, default, integer, candidate, ..Default::default() }))
+        }
+        "boolean" => DataType::Boolean(Box::new(BooleanType { nullable, default, ..Default::default() })),
+        "null" => DataType::Null(Box::new(NullType { default, ..Default::default() })),
+        _ => return Err(invalid(path, format!("unknown type `{}`", name))),
+    };
+    if let Some(keyword) = keywords.keys().next() {
+        return Err(ImportError::Unsupported { path: path.to_string(), keyword: keyword.clone() });
+    }
+    Ok(data_type)
+}
+
+/// keep the pattern's meaning of matching anywhere under say's whole-string matching,
+/// unwrapping what [`Schema::to_json_schema`] wrote
+fn unanchored(pattern: &str) -> String {
+    let exported = pattern.strip_prefix("^(?:").and_then(|pattern| pattern.strip_suffix(")$"));
+    match exported.filter(|inner| is_balanced(inner)) {
+        Some(inner) => inner.to_owned(),
+        None => format!("(?s:.*)(?:{})(?s:.*)", pattern),
+    }
+}
+
+/// whether every group closes inside the pattern, so `a)|(?:b` taken out of `^(?:a)|(?:b)$` is not
+fn is_balanced(pattern: &str) -> bool {
+    let mut depth = 0;
+    let mut in_class = false;
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            '[' if !in_class => in_class = true,
+            ']' if in_class => in_class = false,
+            '(' if !in_class => depth += 1,
+            ')' if !in_class => {
+                if depth == 0 {
+                    return false;
+                }
+                depth -= 1;
+            }
+            _ => {}
+        }
+    }
+    depth == 0 && !in_class
+}
+
+fn set_optional(data_type: &mut DataType, optional: bool) {
+    match data_type {
+        DataType::Dict(inner) => inner.optional = optional,
+        DataType::List(inner) => inner.optional = optional,
+        DataType::String(inner) => inner.optional = optional,
+        DataType::Literal(inner) => inner.optional = optional,
+        DataType::Boolean(inner) => inner.optional = optional,
+        DataType::Number(inner) => inner.optional = optional,
+        DataType::Tuple(inner) => inner.optional = optional,
+        DataType::OneOf(inner) => inner.optional = optional,
+        DataType::Null(inner) => inner.optional = optional,
+        DataType::Ref(inner) => inner.optional = optional,
+        DataType::Dynamic(_) => {}
+    }
+}
+
+/// integral values are written as integers, so `0.0` exports as `0`
+fn number(value: f64) -> Value {
+    if value.fract() == 0.0 && value.abs() < i64::MAX as f64 {
+        json!(value as i64)
+    } else {
+        json!(value)
+    }
+}
+
+/// say patterns match the whole string, JSON Schema ones match anywhere
+fn anchored(pattern: &str) -> String {
+    format!("^(?:{})$", pattern)
+}
+
+fn export(data_type: &DataType) -> Value {
+    let mut object = Map::new();
+    let nullable = match data_type {
+        DataType::Dict(inner) => {
+            object.insert("type".to_owned(), json!("object"));
+            let properties: Map<String, Value> =
+                inner.fields.iter().map(|(key, field)| (key.clone(), export(field))).collect();
+            object.insert("properties".to_owned(), Value::Object(properties));
+            let mut required: Vec<&String> = inner
+                .fields
+                .iter()
+                .filter(|(_, field)| !(inner.all_optional || field.is_optional()))
+                .map(|(key, _)| key)
+                .collect();
+            required.sort();
+            if !required.is_empty() {
+                object.insert("required".to_owned(), json!(required));
+            }
+            if let Some(any_fields) = &inner.any_fields {
+                let patterns = any_fields.iter().map(|(pattern, field)| (anchored(pattern.as_str()), export(field)));
+                object.insert("patternProperties".to_owned(), Value::Object(patterns.collect()));
+            }
+            let others = inner.others.as_ref().map_or(json!(false), export);
+            object.insert("additionalProperties".to_owned(), others);
+            if let Some(min_properties) = inner.min_properties {
+                object.insert("minProperties".to_owned(), json!(min_properties));
+            }
+            if let Some(max_properties) = inner.max_properties {
+                object.insert("maxProperties".to_owned(), json!(max_properties));
+            }
+            if let Some(property_names) = &inner.property_names {
+                object.insert("propertyNames".to_owned(), json!({ "enum": property_names.candidate }));
+            }
+            if let Some(conflicts) = &inner.conflicts {
+                let dependencies = conflicts.iter().map(|(key, conflicting)| {
+                    let present: Vec<Value> = conflicting.iter().map(|field| json!({ "required": [field] })).collect();
+                    (key.clone(), json!({ "not": { "anyOf": present } }))
+                });
+                object.insert("dependencies".to_owned(), Value::Object(dependencies.collect()));
+            }
+            inner.nullable
+        }
+        DataType::List(inner) => {
+            object.insert("type".to_owned(), json!("array"));
+            object.insert("items".to_owned(), export(&inner.element_type));
+            if let Some(max_items) = inner.max_items {
+                object.insert("maxItems".to_owned(), json!(max_items));
+            }
+            inner.nullable
+        }
+        DataType::Tuple(inner) => {
+            object.insert("type".to_owned(), json!("array"));
+            object.insert("items".to_owned(), Value::Array(inner.elements.iter().map(export).collect()));
+            object.insert("minItems".to_owned(), json!(inner.elements.len()));
+            let rest = inner.rest.as_ref().map_or(json!(false), export);
+            object.insert("additionalItems".to_owned(), rest);
+            inner.nullable
+        }
+        DataType::String(inner) => {
+            object.insert("type".to_owned(), json!("string"));
+            // JSON Schema counts code points, which is what `chars` measures
+            if inner.length_unit == LengthUnit::Chars {
+                if let Some(length) = inner.length {
+                    object.insert("maxLength".to_owned(), json!(length));
+                }
+                if let Some(min_length) = inner.min_length {
+                    object.insert("minLength".to_owned(), json!(min_length));
+                }
+            }
+            if let Some(pattern) = &inner.regex {
+                object.insert("pattern".to_owned(), json!(anchored(pattern.as_str())));
+            }
+            let exported_formats = [format::DATE_TIME, format::JSON_POINTER];
+            if let Some(name) = inner.format.as_deref().filter(|name| exported_formats.contains(name)) {
+                object.insert("format".to_owned(), json!(name));
+            }
+            inner.nullable
+        }
+        DataType::Literal(inner) => {
+            object.insert("type".to_owned(), json!("string"));
+            object.insert("enum".to_owned(), json!(inner.candidate));
+            inner.nullable
+        }
+        DataType::Boolean(inner) => {
+            object.insert("type".to_owned(), json!("boolean"));
+            inner.nullable
+        }
+        DataType::Number(inner) => {
+            object.insert("type".to_owned(), json!(if inner.integer { "integer" } else { "number" }));
+            if let Some(minimum) = inner.minimum {
+                let keyword = if inner.exclusive_minimum { "exclusiveMinimum" } else { "minimum" };
+                object.insert(keyword.to_owned(), number(minimum));
+            }
+            if let Some(maximum) = inner.maximum {
+                let keyword = if inner.exclusive_maximum { "exclusiveMaximum" } else { "maximum" };
+                object.insert(keyword.to_owned(), number(maximum));
+            }
+            if let Some(grid) = inner.grid.filter(|grid| grid.offset == 0.0) {
+                object.insert("multipleOf".to_owned(), number(grid.step));
+            }
+            if let Some(candidate) = &inner.candidate {
+                object.insert("enum".to_owned(), Value::Array(candidate.iter().copied().map(number).collect()));
+            }
+            inner.nullable
+        }
+        DataType::Null(_) => {
+            object.insert("type".to_owned(), json!("null"));
+            false
+        }
+        DataType::OneOf(inner) => {
+            object.insert("anyOf".to_owned(), Value::Array(inner.variants.iter().map(export).collect()));
+            inner.nullable
+        }
+        DataType::Ref(inner) => {
+            let reference = json!({ "$ref": format!("#/definitions/{}", inner.name) });
+            // keywords next to `$ref` are ignored in draft-07, so null is allowed around it
+            if inner.nullable {
+                object.insert("anyOf".to_owned(), json!([reference, { "type": "null" }]));
+            } else {
+                object.insert("$ref".to_owned(), reference["$ref"].clone());
+            }
+            false
+        }
+        DataType::Dynamic(_) => false,
+    };
+    if let Some(default) = data_type.default_value() {
+        object.insert("default".to_owned(), default.clone());
+    }
+    if nullable {
+        allow_null(&mut object);
+    }
+    Value::Object(object)
+}
+
+fn allow_null(object: &mut Map<String, Value>) {
+    match object.get_mut("type") {
+        Some(ty) => *ty = json!([ty.take(), "null"]),
+        None => {
+            let variants = object.entry("anyOf").or_insert_with(|| json!([]));
+            variants.as_array_mut().unwrap().push(json!({ "type": "null" }));
+        }
+    }
+    if let Some(Value::Array(candidates)) = object.get_mut("enum") {
+        candidates.push(Value::Null);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use crate::json_schema::ImportError;
+    use crate::schema::Schema;
+
+    #[test]
+    fn to_json_schema_should_match_the_fixture() {
+        let schema: Schema = serde_json::from_str(include_str!("../tests/fixtures/export.schema.json")).unwrap();
+        let expected: Value = serde_json::from_str(include_str!("../tests/fixtures/export.json-schema.json")).unwrap();
+        assert_eq!(expected, schema.to_json_schema());
+    }
+
+    #[test]
+    fn from_json_schema_should_round_trip_exported_schemas() {
+        let roots = vec![
+            json!({ "type": "String", "length": 10, "regex": "[a-z]+|[0-9]+" }),
+            json!({ "type": "Literal", "candidate": ["info", "debug"], "nullable": true }),
+            json!({ "type": "List", "max_items": 3, "element_type": { "type": "Number", "integer": true } }),
+            json!({
+                "type": "Dict",
+                "fields": {
+                    "name": { "type": "String", "default": "kilerd", "optional": true },
+                    "admin": { "type": "Boolean", "nullable": true },
+                    "friends": { "type": "List", "element_type": { "type": "Dict", "fields": {}, "others": { "type": "Null" } } }
+                }
+            }),
+        ];
+        for root in roots {
+            let schema: Schema = serde_json::from_value(json!({ "root": root, "validators": [] })).unwrap();
+            let imported = Schema::from_json_schema(&schema.to_json_schema()).unwrap();
+            assert!(imported.semantically_eq(&schema), "{:?} became {:?}", schema, imported);
+        }
+    }
+
+    #[test]
+    fn from_json_schema_should_import_definitions_and_refs() {
+        let schema: Schema = serde_json::from_value(json!({
+            "root": { "type": "Dict", "fields": { "home": { "type": "Ref", "name": "address" } } },
+            "definitions": { "address": { "type": "Dict", "fields": { "city": { "type": "String", "length": 20 } } } },
+            "validators": []
+        }))
+        .unwrap();
+        let imported = Schema::from_json_schema(&schema.to_json_schema()).unwrap();
+        assert!(imported.semantically_eq(&schema), "{:?} became {:?}", schema, imported);
+        assert!(imported.validate(&json!({ "home": { "city": "Shenzhen" } })));
+        assert!(!imported.validate(&json!({ "home": { "city": 1 } })));
+
+        let remote = Schema::from_json_schema(&json!({ "$ref": "https://example.com/address.json" }));
+        assert!(matches!(remote, Err(ImportError::Invalid { .. })));
+    }
+
+    #[test]
+    fn from_json_schema_should_keep_unanchored_patterns_matching_anywhere() {
+        let imported = Schema::from_json_schema(&json!({ "type": "string", "pattern": "^a|b$" })).unwrap();
+        assert!(imported.validate(&json!("abc")));
+        assert!(imported.validate(&json!("cab")));
+        assert!(!imported.validate(&json!("cac")));
+    }
+
+    #[test]
+    fn from_json_schema_should_reject_unsupported_keywords() {
+        let error = Schema::from_json_schema(&json!({
+            "type": "object",
+            "properties": { "age": { "type": "integer", "minimum": 0 } },
+            "additionalProperties": false
+        }))
+        .unwrap_err();
+        assert_eq!(ImportError::Unsupported { path: "/properties/age".to_owned(), keyword: "minimum".to_owned() }, error);
+        assert_eq!("/properties/age: unsupported keyword `minimum`", error.to_string());
+
+        let open = Schema::from_json_schema(&json!({ "type": "object", "properties": {} }));
+        assert!(matches!(open, Err(ImportError::Invalid { .. })));
+    }
+}
@@ -1,7 +1,21 @@
+pub mod builder;
+pub mod compile;
+pub mod doc;
+pub mod error;
+pub mod example;
+pub mod fill;
+pub mod format;
+pub mod json_schema;
+pub mod loader;
+pub mod migrate;
+pub mod openapi;
 pub mod schema;
 pub mod validator;
+pub mod value;
 
 
+pub use compile::CompiledSchema;
+pub use error::SayError;
 pub use schema::Schema;
 
 #[cfg(test)]
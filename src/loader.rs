@@ -0,0 +1,53 @@
+use serde::de::DeserializeOwned;
+
+use crate::error::SayError;
+
+const BOM: char = '\u{feff}';
+
+/// Parse a schema or data document, tolerating a leading UTF-8 BOM and leading whitespace.
+pub fn from_str<T: DeserializeOwned>(content: &str) -> serde_json::Result<T> {
+    let content = content.strip_prefix(BOM).unwrap_or(content);
+    serde_json::from_str(content.trim_start())
+}
+
+/// Like [`from_str`], for raw bytes that still have to be checked for UTF-8.
+pub fn from_slice<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, SayError> {
+    let content = std::str::from_utf8(bytes).map_err(|err| SayError::InvalidUtf8 { offset: err.valid_up_to() })?;
+    Ok(from_str(content)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use crate::error::SayError;
+    use crate::loader::{from_slice, from_str};
+    use crate::validator::Validator;
+    use crate::Schema;
+
+    #[test]
+    fn should_load_bom_prefixed_document() {
+        let value: Value = from_str("\u{feff}{\"a\": true}").unwrap();
+        assert_eq!(json!({ "a": true }), value);
+    }
+
+    #[test]
+    fn should_load_bom_prefixed_schema_with_leading_whitespace() {
+        let content = "\u{feff}\n  { \"root\": { \"type\": \"Boolean\" }, \"validators\": [] }";
+        let schema: Schema = from_str(content).unwrap();
+        assert!(schema.root.validate_type(&json!(true)));
+    }
+
+    #[test]
+    fn should_only_strip_one_bom() {
+        assert!(from_str::<Value>("\u{feff}\u{feff}{}").is_err());
+    }
+
+    #[test]
+    fn should_report_offset_of_invalid_utf8() {
+        let bytes = b"{\"a\": \"\xe4\xb8\xad\xff\"}";
+        assert!(matches!(from_slice::<Value>(bytes), Err(SayError::InvalidUtf8 { offset: 10 })));
+        assert!(matches!(from_slice::<Value>(b"{\"a\": }"), Err(SayError::Json(_))));
+        assert_eq!(json!({ "a": "中" }), from_slice::<Value>("\u{feff}{\"a\": \"中\"}".as_bytes()).unwrap());
+    }
+}
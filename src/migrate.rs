@@ -0,0 +1,68 @@
+//! Rewriting schema documents written against older versions of the crate.
+
+use serde_json::Value;
+
+/// `(type, old field name, current field name)`, the old names are still read through serde aliases
+const RENAMED_FIELDS: &[(&str, &str, &str)] = &[("List", "limit", "max_items")];
+
+/// Rename every deprecated field in a schema document to its current name,
+/// returning how many fields were renamed.
+pub fn migrate(schema: &mut Value) -> usize {
+    let mut renamed = 0;
+    match schema {
+        Value::Object(object) => {
+            let data_type = object.get("type").and_then(Value::as_str).map(str::to_owned);
+            for (_, old, new) in RENAMED_FIELDS.iter().filter(|(ty, _, _)| data_type.as_deref() == Some(*ty)) {
+                if object.contains_key(*new) {
+                    continue;
+                }
+                if let Some(value) = object.remove(*old) {
+                    object.insert(new.to_string(), value);
+                    renamed += 1;
+                }
+            }
+            for value in object.values_mut() {
+                renamed += migrate(value);
+            }
+        }
+        Value::Array(array) => {
+            for value in array.iter_mut() {
+                renamed += migrate(value);
+            }
+        }
+        _ => {}
+    }
+    renamed
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::migrate::migrate;
+    use crate::schema::Schema;
+
+    #[test]
+    fn migrate_should_rename_limit_to_max_items() {
+        let mut document = json!({
+            "root": {
+                "type": "Dict",
+                "fields": {
+                    "tags": { "type": "List", "limit": 3, "element_type": { "type": "String" } },
+                    "limit": { "type": "Number" }
+                }
+            },
+            "validators": []
+        });
+        let before: Schema = serde_json::from_value(document.clone()).unwrap();
+
+        assert_eq!(1, migrate(&mut document));
+        assert_eq!(json!(3), document["root"]["fields"]["tags"]["max_items"]);
+        assert_eq!(None, document["root"]["fields"]["tags"].get("limit"));
+        assert_eq!(json!({ "type": "Number" }), document["root"]["fields"]["limit"]);
+
+        let after: Schema = serde_json::from_value(document.clone()).unwrap();
+        assert_eq!(before, after);
+        assert_eq!(0, migrate(&mut document));
+    }
+}
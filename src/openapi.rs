@@ -0,0 +1,148 @@
+//! Importing a schema from the `components/schemas` section of an OpenAPI 3 document.
+
+use std::collections::HashSet;
+
+use serde_json::{Map, Value};
+
+use crate::json_schema::ImportError;
+use crate::schema::Schema;
+
+const COMPONENTS: &str = "#/components/schemas/";
+
+impl Schema {
+    /// Import the component `name` through [`Schema::from_json_schema`].
+    ///
+    /// Local refs like `#/components/schemas/Address` become definitions of the same name,
+    /// any other ref fails the import. OpenAPI 3.0's `nullable: true` is read as a `null` type
+    /// next to the declared one.
+    pub fn from_openapi(document: &Value, name: &str) -> Result<Schema, ImportError> {
+        let components = document
+            .pointer("/components/schemas")
+            .and_then(Value::as_object)
+            .ok_or_else(|| invalid("/components/schemas", "expected an object of schemas"))?;
+
+        let mut pending = vec![name.to_owned()];
+        let mut visited = HashSet::new();
+        let mut rewritten = Map::new();
+        let mut referenced = HashSet::new();
+        while let Some(component) = pending.pop() {
+            if !visited.insert(component.clone()) {
+                continue;
+            }
+            let path = format!("/components/schemas/{}", component);
+            let schema = components.get(&component).ok_or_else(|| invalid(&path, "no such component"))?;
+            let mut refs = vec![];
+            rewritten.insert(component, rewrite(schema, &path, &mut refs)?);
+            referenced.extend(refs.iter().cloned());
+            pending.extend(refs);
+        }
+
+        let mut root = rewritten.remove(name).expect("the named component is visited first");
+        // the component itself only turns into a definition when it refers to itself
+        if referenced.contains(name) {
+            rewritten.insert(name.to_owned(), root.clone());
+        }
+        if !rewritten.is_empty() {
+            if let Value::Object(object) = &mut root {
+                object.insert("definitions".to_owned(), Value::Object(rewritten));
+            }
+        }
+        Schema::from_json_schema(&root)
+    }
+}
+
+fn invalid(path: &str, reason: impl Into<String>) -> ImportError {
+    ImportError::Invalid { path: path.to_owned(), reason: reason.into() }
+}
+
+/// the JSON Schema the importer reads, with the names of the components it refers to pushed onto `refs`
+fn rewrite(schema: &Value, path: &str, refs: &mut Vec<String>) -> Result<Value, ImportError> {
+    let mut object = match schema {
+        Value::Object(object) => object.clone(),
+        _ => return Ok(schema.clone()),
+    };
+    if let Some(reference) = object.remove("$ref") {
+        let name = reference.as_str().and_then(|reference| reference.strip_prefix(COMPONENTS));
+        let name = name.ok_or_else(|| invalid(path, format!("only `$ref`s to `{}` are supported", COMPONENTS)))?;
+        refs.push(name.to_owned());
+        object.insert("$ref".to_owned(), Value::String(format!("#/definitions/{}", name)));
+    }
+    if let Some(nullable) = object.remove("nullable") {
+        match (nullable, object.get("type")) {
+            (Value::Bool(false), _) => {}
+            (Value::Bool(true), Some(Value::String(name))) => {
+                let types = Value::Array(vec![Value::String(name.clone()), Value::String("null".to_owned())]);
+                object.insert("type".to_owned(), types);
+            }
+            _ => return Err(invalid(path, "`nullable` needs a single `type` to add `null` to")),
+        }
+    }
+    if let Some(Value::Object(properties)) = object.get_mut("properties") {
+        for (key, property) in properties.iter_mut() {
+            *property = rewrite(property, &format!("{}/properties/{}", path, key), refs)?;
+        }
+    }
+    for keyword in ["items", "additionalProperties"] {
+        if let Some(schema) = object.get_mut(keyword) {
+            *schema = rewrite(schema, &format!("{}/{}", path, keyword), refs)?;
+        }
+    }
+    Ok(Value::Object(object))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::json_schema::ImportError;
+    use crate::schema::Schema;
+
+    #[test]
+    fn from_openapi_should_extract_a_component_with_its_refs() {
+        let document = json!({
+            "openapi": "3.0.3",
+            "info": { "title": "users", "version": "1" },
+            "paths": {},
+            "components": {
+                "schemas": {
+                    "User": {
+                        "type": "object",
+                        "properties": {
+                            "name": { "type": "string", "maxLength": 10 },
+                            "nickname": { "type": "string", "nullable": true },
+                            "home": { "$ref": "#/components/schemas/Address" },
+                            "friends": { "type": "array", "items": { "$ref": "#/components/schemas/User" } }
+                        },
+                        "required": ["name", "home"],
+                        "additionalProperties": false
+                    },
+                    "Address": {
+                        "type": "object",
+                        "properties": { "city": { "type": "string" } },
+                        "required": ["city"],
+                        "additionalProperties": false
+                    },
+                    "Unrelated": { "type": "boolean", "nullable": "yes" }
+                }
+            }
+        });
+        let schema = Schema::from_openapi(&document, "User").unwrap();
+
+        assert!(schema.validate(&json!({ "name": "kilerd", "nickname": null, "home": { "city": "Shenzhen" } })));
+        assert!(schema.validate(&json!({
+            "name": "kilerd",
+            "home": { "city": "Shenzhen" },
+            "friends": [{ "name": "alice", "home": { "city": "Beijing" } }]
+        })));
+        let errors = schema.validate_detailed(&json!({ "name": "kilerd", "home": {} })).unwrap_err();
+        assert_eq!("/home/city", errors[0].path);
+        assert!(!schema.validate(&json!({ "name": "kilerd", "home": { "city": "Shenzhen" }, "friends": [{ "name": 1 }] })));
+
+        assert_eq!(
+            Err(ImportError::Invalid { path: "/components/schemas/Group".to_owned(), reason: "no such component".to_owned() }),
+            Schema::from_openapi(&document, "Group")
+        );
+        let remote = json!({ "components": { "schemas": { "User": { "$ref": "users.yaml#/User" } } } });
+        assert!(matches!(Schema::from_openapi(&remote, "User"), Err(ImportError::Invalid { .. })));
+    }
+}
@@ -1,13 +1,123 @@
+use std::cmp::Ordering;
 use std::collections::HashMap;
-use serde::{Serialize, Deserialize};
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, OnceLock, Weak};
+use serde::{Serialize, Deserialize, Serializer, Deserializer};
+use regex::Regex;
+use serde_json::Value;
 
-#[derive(Debug, Serialize, Deserialize)]
+use crate::compile::Linked;
+use crate::error::{ElementErrors, ErrorTree, HeterogeneousError, ValidationError};
+use crate::validator::{Path, ValidationOptions, Validator};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Schema {
-    root: DataType,
+    pub(crate) root: DataType,
     validators: Vec<String>,
+    /// named types any `Ref` in the schema can point at, including other definitions
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub(crate) definitions: HashMap<String, DataType>,
+}
+
+impl Schema {
+    pub fn new(root: DataType) -> Self {
+        Schema { root, validators: vec![], definitions: HashMap::new() }
+    }
+
+    /// Structural equality that ignores the order of literal and number candidates and of `OneOf` variants.
+    pub fn semantically_eq(&self, other: &Schema) -> bool {
+        let mut left = self.clone();
+        let mut right = other.clone();
+        for schema in [&mut left, &mut right] {
+            schema.root.normalize();
+            for definition in schema.definitions.values_mut() {
+                definition.normalize();
+            }
+        }
+        left == right
+    }
+
+    /// ```
+    /// use say::Schema;
+    /// use serde_json::json;
+    ///
+    /// let schema: Schema = serde_json::from_str(r#"{
+    ///     "root": { "type": "Dict", "fields": { "name": { "type": "String", "length": 10 } } },
+    ///     "validators": []
+    /// }"#).unwrap();
+    /// assert!(schema.validate(&json!({ "name": "kilerd" })));
+    /// assert!(!schema.validate(&json!({ "name": 1 })));
+    /// ```
+    ///
+    /// Refs are linked to a copy of the definitions on every call, compile the schema to do that once.
+    pub fn validate(&self, node: &Value) -> bool {
+        self.linked().validate(node)
+    }
+
+    pub fn validate_detailed(&self, node: &Value) -> Result<(), Vec<ValidationError>> {
+        self.linked().validate_detailed(node)
+    }
+
+    /// The errors of [`Schema::validate_detailed`] arranged by where they are in the document,
+    /// empty when the document is valid.
+    pub fn validate_tree(&self, node: &Value) -> ErrorTree {
+        ErrorTree::from_errors(self.validate_detailed(node).err().unwrap_or_default())
+    }
+
+    /// Validate against every schema, like a base contract and an overlay, and merge their errors sorted by path.
+    /// An error several schemas report alike is only kept once.
+    pub fn validate_all_of(schemas: &[&Schema], node: &Value) -> Vec<ValidationError> {
+        let mut merged: Vec<ValidationError> = vec![];
+        for schema in schemas {
+            for error in schema.validate_detailed(node).err().into_iter().flatten() {
+                if !merged.iter().any(|seen| seen.path == error.path && seen.code == error.code && seen.expected == error.expected) {
+                    merged.push(error);
+                }
+            }
+        }
+        merged.sort_by(|left, right| left.path.cmp(&right.path));
+        merged
+    }
+
+    /// Validate every element of an array against the schema its `discriminator` field names,
+    /// elements whose discriminator is missing or unknown are checked against this schema instead.
+    /// Each schema's refs point into its own definitions.
+    pub fn validate_heterogeneous_array(
+        &self,
+        discriminator: &str,
+        schemas: &HashMap<String, Schema>,
+        value: &Value,
+    ) -> Result<(), HeterogeneousError> {
+        let array = value.as_array().ok_or_else(|| HeterogeneousError::NotAnArray(value.clone()))?;
+        let options = ValidationOptions::default();
+        let fallback = self.linked();
+        let schemas: HashMap<&str, Linked<'_>> = schemas.iter().map(|(name, schema)| (name.as_str(), schema.linked())).collect();
+        let mut elements = vec![];
+        for (index, element) in array.iter().enumerate() {
+            let chosen = element
+                .get(discriminator)
+                .and_then(Value::as_str)
+                .and_then(|name| schemas.get_key_value(name));
+            let (schema, root) = match chosen {
+                Some((name, schema)) => (Some(name.to_string()), schema),
+                None => (None, &fallback),
+            };
+            let mut errors = vec![];
+            root.collect_errors(element, &options, &Path::Root.index(index), &mut errors);
+            if !errors.is_empty() {
+                elements.push(ElementErrors { index, schema, errors });
+            }
+        }
+        if elements.is_empty() {
+            Ok(())
+        } else {
+            Err(HeterogeneousError::Elements(elements))
+        }
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(tag = "type")]
 pub enum DataType {
     Dict(Box<DictType>),
@@ -16,60 +126,981 @@ pub enum DataType {
     Literal(Box<LiteralType>),
     Boolean(Box<BooleanType>),
     Number(Box<NumberType>),
+    Tuple(Box<TupleType>),
+    OneOf(Box<OneOfType>),
+    Null(Box<NullType>),
+    /// a named type from the schema's `definitions`, linked when the schema is compiled or validated
+    Ref(Box<RefType>),
+    /// picked at runtime by a callback, only available when building schemas in code
+    #[serde(skip)]
+    Dynamic(DynamicType),
+}
+
+impl DataType {
+    pub fn is_optional(&self) -> bool {
+        match self {
+            DataType::Dict(inner) => inner.optional,
+            DataType::List(inner) => inner.optional,
+            DataType::String(inner) => inner.optional,
+            DataType::Literal(inner) => inner.optional,
+            DataType::Boolean(inner) => inner.optional,
+            DataType::Number(inner) => inner.optional,
+            DataType::Tuple(inner) => inner.optional,
+            DataType::OneOf(inner) => inner.optional,
+            DataType::Null(inner) => inner.optional,
+            DataType::Ref(inner) => inner.optional,
+            DataType::Dynamic(_) => false,
+        }
+    }
+
+    pub fn default_value(&self) -> Option<&Value> {
+        match self {
+            DataType::Dict(inner) => inner.default.as_ref(),
+            DataType::List(inner) => inner.default.as_ref(),
+            DataType::String(inner) => inner.default.as_ref(),
+            DataType::Literal(inner) => inner.default.as_ref(),
+            DataType::Boolean(inner) => inner.default.as_ref(),
+            DataType::Number(inner) => inner.default.as_ref(),
+            DataType::Tuple(inner) => inner.default.as_ref(),
+            DataType::OneOf(inner) => inner.default.as_ref(),
+            DataType::Null(inner) => inner.default.as_ref(),
+            DataType::Ref(_) | DataType::Dynamic(_) => None,
+        }
+    }
+
+    pub fn description(&self) -> Option<&str> {
+        match self {
+            DataType::Dict(inner) => inner.description.as_deref(),
+            DataType::List(inner) => inner.description.as_deref(),
+            DataType::String(inner) => inner.description.as_deref(),
+            DataType::Literal(inner) => inner.description.as_deref(),
+            DataType::Boolean(inner) => inner.description.as_deref(),
+            DataType::Number(inner) => inner.description.as_deref(),
+            DataType::Tuple(inner) => inner.description.as_deref(),
+            DataType::OneOf(inner) => inner.description.as_deref(),
+            DataType::Null(inner) => inner.description.as_deref(),
+            DataType::Ref(inner) => inner.description.as_deref(),
+            DataType::Dynamic(_) => None,
+        }
+    }
+
+    /// sort everything whose order carries no meaning
+    fn normalize(&mut self) {
+        match self {
+            DataType::Dict(inner) => {
+                for field in inner.fields.values_mut() {
+                    field.normalize();
+                }
+                if let Some(any_fields) = &mut inner.any_fields {
+                    for field in any_fields.values_mut() {
+                        field.normalize();
+                    }
+                }
+                if let Some(others) = &mut inner.others {
+                    others.normalize();
+                }
+                if let Some(property_names) = &mut inner.property_names {
+                    property_names.candidate.sort();
+                    property_names.candidate.dedup();
+                }
+            }
+            DataType::List(inner) => inner.element_type.normalize(),
+            DataType::Tuple(inner) => {
+                for element in inner.elements.iter_mut().chain(inner.rest.iter_mut()) {
+                    element.normalize();
+                }
+            }
+            DataType::Literal(inner) => {
+                inner.candidate.sort();
+                inner.candidate.dedup();
+            }
+            DataType::Number(inner) => {
+                if let Some(candidate) = &mut inner.candidate {
+                    candidate.sort_by(f64::total_cmp);
+                    candidate.dedup();
+                }
+            }
+            DataType::OneOf(inner) => {
+                for variant in inner.variants.iter_mut() {
+                    variant.normalize();
+                }
+                // the variants are a multiset, so any order will do as long as equal variants sort alike,
+                // which their serialized form does as objects serialize with sorted keys
+                inner.variants.sort_by_cached_key(|variant| serde_json::to_value(variant).ok().map(|value| value.to_string()));
+            }
+            DataType::Dynamic(inner) => {
+                for variant in inner.variants.iter_mut() {
+                    variant.normalize();
+                }
+            }
+            DataType::String(_) | DataType::Boolean(_) | DataType::Null(_) | DataType::Ref(_) => {}
+        }
+    }
+
+    /// the types nested directly inside this one, refs are not followed
+    pub(crate) fn children(&self) -> Vec<&DataType> {
+        match self {
+            DataType::Dict(inner) => {
+                let any_fields = inner.any_fields.iter().flat_map(|any_fields| any_fields.values());
+                inner.fields.values().chain(any_fields).chain(inner.others.iter()).collect()
+            }
+            DataType::List(inner) => vec![&inner.element_type],
+            DataType::Tuple(inner) => inner.elements.iter().chain(inner.rest.iter()).collect(),
+            DataType::OneOf(inner) => inner.variants.iter().collect(),
+            DataType::Dynamic(inner) => inner.variants.iter().collect(),
+            DataType::String(_) | DataType::Literal(_) | DataType::Boolean(_) | DataType::Number(_) | DataType::Null(_) | DataType::Ref(_) => vec![],
+        }
+    }
+
+    /// like [`DataType::children`], for rewriting them in place
+    pub(crate) fn children_mut(&mut self) -> Vec<&mut DataType> {
+        match self {
+            DataType::Dict(inner) => {
+                let any_fields = inner.any_fields.iter_mut().flat_map(|any_fields| any_fields.values_mut());
+                inner.fields.values_mut().chain(any_fields).chain(inner.others.iter_mut()).collect()
+            }
+            DataType::List(inner) => vec![&mut inner.element_type],
+            DataType::Tuple(inner) => inner.elements.iter_mut().chain(inner.rest.iter_mut()).collect(),
+            DataType::OneOf(inner) => inner.variants.iter_mut().collect(),
+            DataType::Dynamic(inner) => inner.variants.iter_mut().collect(),
+            DataType::String(_) | DataType::Literal(_) | DataType::Boolean(_) | DataType::Number(_) | DataType::Null(_) | DataType::Ref(_) => vec![],
+        }
+    }
+
+    /// Take over what a ref to this type adds to it, the ref's `optional`, its `nullable` and its description,
+    /// for replacing the ref with a copy of the type. `false` when the type has no room for them.
+    pub(crate) fn inherit_ref(&mut self, reference: &RefType) -> bool {
+        let (optional, nullable, description) = match self {
+            DataType::Dict(inner) => (&mut inner.optional, Some(&mut inner.nullable), &mut inner.description),
+            DataType::List(inner) => (&mut inner.optional, Some(&mut inner.nullable), &mut inner.description),
+            DataType::String(inner) => (&mut inner.optional, Some(&mut inner.nullable), &mut inner.description),
+            DataType::Literal(inner) => (&mut inner.optional, Some(&mut inner.nullable), &mut inner.description),
+            DataType::Boolean(inner) => (&mut inner.optional, Some(&mut inner.nullable), &mut inner.description),
+            DataType::Number(inner) => (&mut inner.optional, Some(&mut inner.nullable), &mut inner.description),
+            DataType::Tuple(inner) => (&mut inner.optional, Some(&mut inner.nullable), &mut inner.description),
+            DataType::OneOf(inner) => (&mut inner.optional, Some(&mut inner.nullable), &mut inner.description),
+            // it takes `null` anyway
+            DataType::Null(inner) => (&mut inner.optional, None, &mut inner.description),
+            DataType::Ref(inner) => (&mut inner.optional, Some(&mut inner.nullable), &mut inner.description),
+            DataType::Dynamic(_) => return false,
+        };
+        *optional = reference.optional;
+        if let Some(nullable) = nullable {
+            *nullable |= reference.nullable;
+        }
+        if reference.description.is_some() {
+            description.clone_from(&reference.description);
+        }
+        true
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct DictType {
     #[serde(default = "bool::default")]
     pub optional: bool,
     #[serde(default = "bool::default")]
     pub nullable: bool,
+    #[serde(default)]
+    pub features: Vec<String>,
+    /// inserted by [`Schema::apply_defaults`] when the field is absent, checked against the type when compiling
+    pub default: Option<Value>,
+    /// free text for people reading the schema, shown by [`Schema::to_markdown`] and never validated
+    pub description: Option<String>,
+    /// A field's `optional` decides whether it may be absent, its `nullable` whether it may be `null`:
+    ///
+    /// | `optional` | `nullable` | absent   | `null`   |
+    /// |------------|------------|----------|----------|
+    /// | false      | false      | rejected | rejected |
+    /// | false      | true       | rejected | accepted |
+    /// | true       | false      | accepted | rejected |
+    /// | true       | true       | accepted | accepted |
     pub fields: HashMap<String, DataType>,
-    pub any_fields: Option<HashMap<String, DataType>>,
+    /// keys not declared in `fields` are matched against these patterns,
+    /// the value has to satisfy the type of every pattern its key matches
+    pub any_fields: Option<HashMap<Pattern, DataType>>,
     pub others: Option<DataType>,
+    /// `Some(1)` rejects an empty object while still allowing any subset of the fields
+    pub min_properties: Option<u64>,
+    pub max_properties: Option<u64>,
+    /// when the key is present, none of the listed fields may be
+    pub conflicts: Option<HashMap<String, Vec<String>>>,
+    /// the keys must be exactly `"0"` to `"n-1"`, as written by serializers turning arrays into objects
+    #[serde(default = "bool::default")]
+    pub integer_key_sequence: bool,
+    /// no two values may be equal, with `1` and `1.0` counting as equal
+    #[serde(default = "bool::default")]
+    pub unique_values: bool,
+    /// when not empty, the object's key set must equal exactly one of these
+    #[serde(default)]
+    pub shapes: Vec<Vec<String>>,
+    /// maps a count field to the array field whose length it must equal, like `{"count": "items"}`
+    pub length_of: Option<HashMap<String, String>>,
+    /// treat every field declared here as optional, as for patch documents,
+    /// nested dicts keep their own setting
+    #[serde(default = "bool::default")]
+    pub all_optional: bool,
+    /// every key must be one of these candidates, combine with `others` to type the values of an enum-keyed map
+    pub property_names: Option<LiteralType>,
+    /// treat the object as a graph whose keys are nodes, every edge must point at a declared node
+    pub graph_integrity: Option<GraphIntegrity>,
+    /// every group of array fields must have the same length, as for column-oriented data like `names` and `ages`
+    #[serde(default)]
+    pub parallel_arrays: Vec<Vec<String>>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// How the values of a dict list the nodes their key depends on, like `{"app": ["db"], "db": []}`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GraphIntegrity {
+    /// the field of each value holding its edges, when absent the value itself is the list of edges
+    pub edges: Option<String>,
+    #[serde(default = "bool::default")]
+    pub acyclic: bool,
+}
+
+impl GraphIntegrity {
+    /// the nodes a value points at along with their index in its edge list, anything not a string is skipped
+    pub fn edges<'a>(&self, value: &'a Value) -> Vec<(usize, &'a str)> {
+        let list = match &self.edges {
+            Some(field) => value.get(field),
+            None => Some(value),
+        };
+        list.and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .enumerate()
+            .filter_map(|(index, edge)| edge.as_str().map(|edge| (index, edge)))
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ListType {
     #[serde(default = "bool::default")]
     pub optional: bool,
     #[serde(default = "bool::default")]
     pub nullable: bool,
+    #[serde(default)]
+    pub features: Vec<String>,
+    pub default: Option<Value>,
+    pub description: Option<String>,
     pub element_type: DataType,
-    pub limit: Option<u64>,
+    #[serde(alias = "limit")]
+    pub max_items: Option<u64>,
+    /// the elements must be exactly these values in any order, with nothing missing or extra
+    pub exact_set: Option<Vec<Value>>,
+    pub element_relation: Option<ElementRelation>,
+    /// the value at this path of keys must strictly increase from one element to the next
+    pub strictly_sorted_by: Option<Vec<String>>,
+    /// no two elements may be equal once these keys are removed from them,
+    /// for dicts carrying volatile fields like timestamps
+    pub unique_ignoring: Option<Vec<String>>,
+    /// the elements must be non-negative numbers adding up to 1
+    pub probability_distribution: Option<ProbabilityDistribution>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ProbabilityDistribution {
+    /// how far the sum may stray from 1, something like `1e-9` absorbs the rounding of decimal weights
+    pub tolerance: f64,
+}
+
+/// An array of fixed length whose every position has its own type, like `["kilerd", 24, true]`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TupleType {
+    #[serde(default = "bool::default")]
+    pub optional: bool,
+    #[serde(default = "bool::default")]
+    pub nullable: bool,
+    #[serde(default)]
+    pub features: Vec<String>,
+    pub default: Option<Value>,
+    pub description: Option<String>,
+    pub elements: Vec<DataType>,
+    /// the type of any elements after the declared ones, without it the array must have exactly `elements.len()` items
+    pub rest: Option<DataType>,
+}
+
+impl TupleType {
+    /// the type of the element at this position, `None` past the end of a tuple without `rest`
+    pub fn element(&self, index: usize) -> Option<&DataType> {
+        self.elements.get(index).or(self.rest.as_ref())
+    }
+}
+
+/// A value any one of the variants accepts, like a port given either as a number or as a string.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct OneOfType {
+    #[serde(default = "bool::default")]
+    pub optional: bool,
+    #[serde(default = "bool::default")]
+    pub nullable: bool,
+    #[serde(default)]
+    pub features: Vec<String>,
+    pub default: Option<Value>,
+    pub description: Option<String>,
+    /// tried in order, a value matching several of them is fine
+    pub variants: Vec<DataType>,
+}
+
+/// A use of a named definition, like `{"type": "Ref", "name": "address"}`.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct RefType {
+    #[serde(default = "bool::default")]
+    pub optional: bool,
+    #[serde(default = "bool::default")]
+    pub nullable: bool,
+    pub name: String,
+    pub description: Option<String>,
+    /// set when compiling, the definition itself is owned by the [`CompiledSchema`](crate::CompiledSchema).
+    /// A clone starts out unlinked, it may well outlive that owner.
+    #[serde(skip)]
+    target: OnceLock<Weak<DataType>>,
+}
+
+impl RefType {
+    pub fn new(name: impl Into<String>) -> Self {
+        RefType {
+            name: name.into(),
+            ..Default::default()
+        }
+    }
+
+    /// `None` before compiling, or once the compiled schema is gone
+    pub fn target(&self) -> Option<Arc<DataType>> {
+        self.target.get().and_then(Weak::upgrade)
+    }
+
+    pub(crate) fn link(&self, target: &Arc<DataType>) {
+        let _ = self.target.set(Arc::downgrade(target));
+    }
+}
+
+impl Clone for RefType {
+    fn clone(&self) -> Self {
+        RefType {
+            optional: self.optional,
+            nullable: self.nullable,
+            name: self.name.clone(),
+            description: self.description.clone(),
+            target: OnceLock::new(),
+        }
+    }
+}
+
+impl PartialEq for RefType {
+    fn eq(&self, other: &Self) -> bool {
+        self.optional == other.optional && self.nullable == other.nullable && self.name == other.name
+    }
+}
+
+/// A comparison every element must satisfy against the element before it,
+/// like `previous.end <= current.start` for non-overlapping intervals.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ElementRelation {
+    /// the field of the previous element
+    pub previous: String,
+    pub operator: Comparison,
+    /// the field of the current element
+    pub current: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Comparison {
+    #[serde(rename = "<")]
+    Less,
+    #[serde(rename = "<=")]
+    LessOrEqual,
+    #[serde(rename = "==")]
+    Equal,
+    #[serde(rename = "!=")]
+    NotEqual,
+    #[serde(rename = ">=")]
+    GreaterOrEqual,
+    #[serde(rename = ">")]
+    Greater,
+}
+
+impl fmt::Display for Comparison {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let symbol = match self {
+            Comparison::Less => "<",
+            Comparison::LessOrEqual => "<=",
+            Comparison::Equal => "==",
+            Comparison::NotEqual => "!=",
+            Comparison::GreaterOrEqual => ">=",
+            Comparison::Greater => ">",
+        };
+        f.write_str(symbol)
+    }
+}
+
+impl Comparison {
+    pub fn holds(&self, ordering: Ordering) -> bool {
+        match self {
+            Comparison::Less => ordering == Ordering::Less,
+            Comparison::LessOrEqual => ordering != Ordering::Greater,
+            Comparison::Equal => ordering == Ordering::Equal,
+            Comparison::NotEqual => ordering != Ordering::Equal,
+            Comparison::GreaterOrEqual => ordering != Ordering::Less,
+            Comparison::Greater => ordering == Ordering::Greater,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct LiteralType {
     #[serde(default = "bool::default")]
     pub optional: bool,
     #[serde(default = "bool::default")]
     pub nullable: bool,
+    #[serde(default)]
+    pub features: Vec<String>,
+    pub default: Option<Value>,
+    pub description: Option<String>,
+    /// kept in order: the first is the default [`Schema::sample`] uses, and errors list them all as written
     pub candidate: Vec<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct StringType {
     #[serde(default = "bool::default")]
     pub optional: bool,
     #[serde(default = "bool::default")]
     pub nullable: bool,
+    #[serde(default)]
+    pub features: Vec<String>,
+    pub default: Option<Value>,
+    pub description: Option<String>,
     pub length: Option<u64>,
-    pub regex: Option<String>,
+    pub min_length: Option<u64>,
+    /// what `length` and `min_length` count, characters unless stated otherwise
+    #[serde(default)]
+    pub length_unit: LengthUnit,
+    pub regex: Option<Pattern>,
+    pub format: Option<String>,
+    /// for the `date-time` format, the value must be later than this bound,
+    /// either a date-time or `now` optionally shifted like `now-30d`
+    pub after: Option<String>,
+    /// for the `date-time` format, the value must be earlier than this bound
+    pub before: Option<String>,
+    /// for the `date-time` format, reject local date-times without `Z` or an explicit offset
+    #[serde(default = "bool::default")]
+    pub require_offset: bool,
+    /// for the `date-time` format, the offset must be zero, as in `Z` or `+00:00`
+    #[serde(default = "bool::default")]
+    pub require_utc: bool,
+    /// for the `decimal` format, the thousands separator, no grouping is accepted when `None`
+    pub grouping: Option<char>,
+    /// for the `decimal` format, defaults to `.`
+    pub decimal_separator: Option<char>,
+    /// for the `hex` and `binary` formats, the most significant bits the value may use
+    pub bit_width: Option<u32>,
+    /// for the `uuid` format, the version the value must have, like `4` for random ones
+    pub uuid_version: Option<u8>,
+    /// a database column type the value has to fit, like `varchar(255)` or `text`
+    pub column: Option<String>,
+    /// a strftime pattern like `%Y-%m-%d %H:%M:%S` the value must parse under
+    pub strftime: Option<String>,
+    /// reject values with leading or trailing whitespace instead of trimming them
+    #[serde(default = "bool::default")]
+    pub no_surrounding_whitespace: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LengthUnit {
+    /// UTF-8 bytes
+    Bytes,
+    /// unicode scalar values
+    #[default]
+    Chars,
+    /// extended grapheme clusters, what a reader sees as one character
+    #[cfg(feature = "unicode-segmentation")]
+    Graphemes,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+impl LengthUnit {
+    pub fn name(&self) -> &'static str {
+        match self {
+            LengthUnit::Bytes => "bytes",
+            LengthUnit::Chars => "characters",
+            #[cfg(feature = "unicode-segmentation")]
+            LengthUnit::Graphemes => "graphemes",
+        }
+    }
+
+    pub fn measure(&self, input: &str) -> u64 {
+        match self {
+            LengthUnit::Bytes => input.len() as u64,
+            LengthUnit::Chars => input.chars().count() as u64,
+            #[cfg(feature = "unicode-segmentation")]
+            LengthUnit::Graphemes => {
+                use unicode_segmentation::UnicodeSegmentation;
+                input.graphemes(true).count() as u64
+            }
+        }
+    }
+}
+
+/// The length bound of a database column type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnLimit {
+    Chars(u64),
+    Bytes(u64),
+}
+
+impl ColumnLimit {
+    /// `char(n)` and `varchar(n)` count characters, the MySQL text types count bytes
+    pub fn parse(spec: &str) -> Option<ColumnLimit> {
+        let spec = spec.trim().to_ascii_lowercase();
+        match spec.as_str() {
+            "tinytext" => return Some(ColumnLimit::Bytes(255)),
+            "text" => return Some(ColumnLimit::Bytes(65_535)),
+            "mediumtext" => return Some(ColumnLimit::Bytes(16_777_215)),
+            "longtext" => return Some(ColumnLimit::Bytes(4_294_967_295)),
+            _ => {}
+        }
+        let (name, length) = spec.strip_suffix(')')?.split_once('(')?;
+        let length = length.trim().parse().ok()?;
+        match name.trim() {
+            "char" | "varchar" | "character" | "character varying" | "nchar" | "nvarchar" => {
+                Some(ColumnLimit::Chars(length))
+            }
+            _ => None,
+        }
+    }
+
+    pub fn fits(&self, value: &str) -> bool {
+        match self {
+            ColumnLimit::Chars(limit) => value.chars().count() as u64 <= *limit,
+            ColumnLimit::Bytes(limit) => value.len() as u64 <= *limit,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct BooleanType {
     #[serde(default = "bool::default")]
     pub optional: bool,
     #[serde(default = "bool::default")]
     pub nullable: bool,
+    #[serde(default)]
+    pub features: Vec<String>,
+    pub default: Option<Value>,
+    pub description: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// Exactly `null`, mostly useful as a `OneOf` variant, it has no `nullable` since it accepts nothing else.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct NullType {
+    #[serde(default = "bool::default")]
+    pub optional: bool,
+    #[serde(default)]
+    pub features: Vec<String>,
+    pub default: Option<Value>,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct NumberType {
     #[serde(default = "bool::default")]
     pub optional: bool,
     #[serde(default = "bool::default")]
     pub nullable: bool,
+    #[serde(default)]
+    pub features: Vec<String>,
+    pub default: Option<Value>,
+    pub description: Option<String>,
+    /// integers must stay within `±(2^53 - 1)` so that a JavaScript consumer reads them exactly
+    #[serde(default = "bool::default")]
+    pub safe_integer: bool,
+    /// reject numbers with a fractional part, `2.0` still counts as an integer
+    #[serde(default = "bool::default")]
+    pub integer: bool,
+    /// the value must survive a round trip through `f32` unchanged
+    #[serde(default = "bool::default")]
+    pub fits_f32: bool,
+    pub format: Option<NumberFormat>,
+    pub minimum: Option<f64>,
+    pub maximum: Option<f64>,
+    /// whether a value equal to `minimum` is rejected
+    #[serde(default = "bool::default")]
+    pub exclusive_minimum: bool,
+    /// whether a value equal to `maximum` is rejected
+    #[serde(default = "bool::default")]
+    pub exclusive_maximum: bool,
+    pub grid: Option<Grid>,
+    /// the value must equal one of these exactly, `2` and `2.0` are the same number but `0.1 + 0.2` is not `0.3`
+    pub candidate: Option<Vec<f64>>,
+}
+
+/// Values allowed at `offset + n * step` for any integer `n`, like `0.5, 1.5, 2.5` for a step of 1 and offset of 0.5.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Grid {
+    pub step: f64,
+    #[serde(default)]
+    pub offset: f64,
+}
+
+impl Grid {
+    /// tolerates the rounding error of decimal steps like `0.1`
+    pub fn contains(&self, value: f64) -> bool {
+        let steps = (value - self.offset) / self.step;
+        steps.is_finite() && (steps - steps.round()).abs() < 1e-9
+    }
+}
+
+/// Well-known numeric ranges, both ends inclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NumberFormat {
+    /// `0` to `1`
+    Ratio,
+    /// `0` to `100`
+    Percentage,
+    /// an integer from `1` to `65535`
+    Port,
+    /// an integer from `100` to `599`
+    HttpStatus,
+}
+
+impl NumberFormat {
+    pub fn name(&self) -> &'static str {
+        match self {
+            NumberFormat::Ratio => "ratio",
+            NumberFormat::Percentage => "percentage",
+            NumberFormat::Port => "port",
+            NumberFormat::HttpStatus => "http_status",
+        }
+    }
+
+    pub fn contains(&self, value: f64) -> bool {
+        match self {
+            NumberFormat::Ratio => (0.0..=1.0).contains(&value),
+            NumberFormat::Percentage => (0.0..=100.0).contains(&value),
+            NumberFormat::Port => value.fract() == 0.0 && (1.0..=65535.0).contains(&value),
+            NumberFormat::HttpStatus => value.fract() == 0.0 && (100.0..=599.0).contains(&value),
+        }
+    }
+}
+
+pub type Selector = dyn Fn(&Value) -> Option<usize> + Send + Sync;
+
+/// Validates a node against one of several variants, chosen by inspecting the node.
+///
+/// The selector returns the index of the variant to apply, a node it returns `None` for is invalid.
+#[derive(Clone)]
+pub struct DynamicType {
+    pub variants: Vec<DataType>,
+    selector: Arc<Selector>,
+}
+
+impl DynamicType {
+    pub fn new(variants: Vec<DataType>, selector: impl Fn(&Value) -> Option<usize> + Send + Sync + 'static) -> Self {
+        DynamicType {
+            variants,
+            selector: Arc::new(selector),
+        }
+    }
+
+    pub fn select(&self, node: &Value) -> Option<&DataType> {
+        (self.selector)(node).and_then(|index| self.variants.get(index))
+    }
+}
+
+impl fmt::Debug for DynamicType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DynamicType").field("variants", &self.variants).finish_non_exhaustive()
+    }
+}
+
+impl PartialEq for DynamicType {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.selector, &other.selector) && self.variants == other.variants
+    }
+}
+
+/// A regex pattern as written in the schema, compiled on first use.
+///
+/// The compiled regex is kept behind an `Arc` so that a [`CompiledSchema`](crate::CompiledSchema)
+/// can hand the same instance to every pattern sharing the same source.
+#[derive(Clone)]
+pub struct Pattern {
+    source: String,
+    compiled: OnceLock<Arc<Regex>>,
+}
+
+impl Pattern {
+    pub fn new(source: impl Into<String>) -> Self {
+        Pattern {
+            source: source.into(),
+            compiled: OnceLock::new(),
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+
+    /// the pattern has to match the whole string, grouped so that `a|b` means `^(?:a|b)$` and not `^a|b$`
+    pub fn compile(&self) -> Result<Regex, regex::Error> {
+        Regex::new(&format!("^(?:{})$", self.source))
+    }
+
+    pub fn regex(&self) -> Result<&Regex, regex::Error> {
+        if let Some(regex) = self.compiled.get() {
+            return Ok(regex);
+        }
+        let regex = self.compile()?;
+        Ok(self.compiled.get_or_init(|| Arc::new(regex)))
+    }
+
+    /// a pattern that does not compile matches nothing, [`Schema::compile`] reports it up front
+    pub fn is_match(&self, input: &str) -> bool {
+        self.regex().is_ok_and(|regex| regex.is_match(input))
+    }
+
+    pub(crate) fn share(&self, regex: Arc<Regex>) {
+        let _ = self.compiled.set(regex);
+    }
+}
+
+impl From<&str> for Pattern {
+    fn from(source: &str) -> Self {
+        Pattern::new(source)
+    }
+}
+
+impl From<String> for Pattern {
+    fn from(source: String) -> Self {
+        Pattern::new(source)
+    }
+}
+
+impl PartialEq for Pattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.source == other.source
+    }
+}
+
+impl Eq for Pattern {}
+
+/// only the source is hashed, the lazily compiled regex never changes what a pattern is
+impl Hash for Pattern {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.source.hash(state);
+    }
+}
+
+impl fmt::Debug for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Pattern").field(&self.source).finish()
+    }
+}
+
+impl Serialize for Pattern {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.source)
+    }
+}
+
+impl<'de> Deserialize<'de> for Pattern {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(Pattern::new)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use serde_json::json;
+
+    use crate::error::{ErrorCode, ErrorTree, HeterogeneousError};
+    use crate::schema::{ColumnLimit, Schema};
+
+    fn schema(root: serde_json::Value) -> Schema {
+        serde_json::from_value(json!({ "root": root, "validators": [] })).unwrap()
+    }
+
+    #[test]
+    fn schemas_differing_in_candidate_order_should_be_semantically_equal() {
+        let left = schema(json!({
+            "type": "Dict",
+            "fields": {
+                "a": { "type": "Literal", "candidate": ["x", "y", "z"] },
+                "b": { "type": "List", "element_type": { "type": "Literal", "candidate": ["1", "2"] } }
+            }
+        }));
+        let right = schema(json!({
+            "type": "Dict",
+            "fields": {
+                "b": { "type": "List", "element_type": { "type": "Literal", "candidate": ["2", "1"] } },
+                "a": { "type": "Literal", "candidate": ["z", "x", "y"] }
+            }
+        }));
+        assert_ne!(left, right);
+        assert!(left.semantically_eq(&right));
+    }
+
+    #[test]
+    fn schemas_differing_in_variant_order_should_be_semantically_equal() {
+        let left = schema(json!({ "type": "OneOf", "variants": [{ "type": "String" }, { "type": "Boolean" }] }));
+        let right = schema(json!({ "type": "OneOf", "variants": [{ "type": "Boolean" }, { "type": "String" }] }));
+        assert_ne!(left, right);
+        assert!(left.semantically_eq(&right));
+
+        let nested = schema(json!({
+            "type": "OneOf",
+            "variants": [{ "type": "Literal", "candidate": ["b", "a"] }, { "type": "Null" }, { "type": "Null" }]
+        }));
+        let reordered = schema(json!({
+            "type": "OneOf",
+            "variants": [{ "type": "Null" }, { "type": "Literal", "candidate": ["a", "b"] }, { "type": "Null" }]
+        }));
+        assert!(nested.semantically_eq(&reordered));
+        let fewer = schema(json!({ "type": "OneOf", "variants": [{ "type": "Null" }, { "type": "Literal", "candidate": ["a", "b"] }] }));
+        assert!(!nested.semantically_eq(&fewer));
+    }
+
+    #[test]
+    fn schemas_differing_in_number_candidate_order_should_be_semantically_equal() {
+        let left = schema(json!({ "type": "Number", "candidate": [1, 2] }));
+        let right = schema(json!({ "type": "Number", "candidate": [2, 1] }));
+        assert_ne!(left, right);
+        assert!(left.semantically_eq(&right));
+        assert!(!left.semantically_eq(&schema(json!({ "type": "Number", "candidate": [1, 3] }))));
+    }
+
+    #[test]
+    fn schemas_differing_in_candidates_should_not_be_semantically_equal() {
+        let left = schema(json!({ "type": "Literal", "candidate": ["x", "y"] }));
+        let right = schema(json!({ "type": "Literal", "candidate": ["x", "z"] }));
+        assert!(!left.semantically_eq(&right));
+
+        let left = schema(json!({ "type": "String", "regex": "[a-z]+" }));
+        let right = schema(json!({ "type": "String", "regex": "[a-z]*" }));
+        assert!(!left.semantically_eq(&right));
+    }
+
+    #[test]
+    fn should_parse_column_limit() {
+        assert_eq!(Some(ColumnLimit::Chars(255)), ColumnLimit::parse("varchar(255)"));
+        assert_eq!(Some(ColumnLimit::Chars(10)), ColumnLimit::parse("CHAR( 10 )"));
+        assert_eq!(Some(ColumnLimit::Chars(64)), ColumnLimit::parse("character varying(64)"));
+        assert_eq!(Some(ColumnLimit::Bytes(65_535)), ColumnLimit::parse("TEXT"));
+        assert_eq!(None, ColumnLimit::parse("varchar"));
+        assert_eq!(None, ColumnLimit::parse("varchar(-1)"));
+        assert_eq!(None, ColumnLimit::parse("int(11)"));
+    }
+
+    #[test]
+    fn heterogeneous_array_should_pick_a_schema_per_element() {
+        let mut schemas = HashMap::new();
+        schemas.insert("user".to_owned(), schema(json!({
+            "type": "Dict",
+            "fields": { "type": { "type": "String" }, "email": { "type": "String", "regex": ".+@.+" } }
+        })));
+        schemas.insert("group".to_owned(), schema(json!({
+            "type": "Dict",
+            "fields": { "type": { "type": "String" }, "members": { "type": "Number" } }
+        })));
+        let fallback = schema(json!({ "type": "Dict", "fields": { "type": { "type": "String" } } }));
+
+        let records = json!([
+            { "type": "user", "email": "kilerd@example.com" },
+            { "type": "group", "members": 3 },
+            { "type": "user", "email": "nope" },
+            { "type": "group", "members": "3" },
+            { "type": "device" }
+        ]);
+        let elements = match fallback.validate_heterogeneous_array("type", &schemas, &records) {
+            Err(HeterogeneousError::Elements(elements)) => elements,
+            other => panic!("unexpected {:?}", other),
+        };
+        assert_eq!(2, elements.len());
+        assert_eq!((2, Some("user")), (elements[0].index, elements[0].schema.as_deref()));
+        assert_eq!("/2/email", elements[0].errors[0].path);
+        assert_eq!((3, Some("group")), (elements[1].index, elements[1].schema.as_deref()));
+        assert_eq!(ErrorCode::WrongType, elements[1].errors[0].code);
+
+        assert!(matches!(
+            fallback.validate_heterogeneous_array("type", &schemas, &json!({})),
+            Err(HeterogeneousError::NotAnArray(_))
+        ));
+        assert_eq!(Ok(()), fallback.validate_heterogeneous_array("type", &schemas, &json!([{ "type": "device" }])));
+    }
+
+    #[test]
+    fn validate_all_of_should_merge_the_errors_of_every_schema() {
+        let base = schema(json!({ "type": "Dict", "fields": { "id": { "type": "Number" } }, "others": { "type": "String" } }));
+        let overlay = schema(json!({ "type": "Dict", "fields": { "id": { "type": "Number" }, "tenant": { "type": "String", "length": 3 } } }));
+
+        assert!(Schema::validate_all_of(&[&base, &overlay], &json!({ "id": 1, "tenant": "abc" })).is_empty());
+
+        // only the overlay limits the tenant
+        let errors = Schema::validate_all_of(&[&base, &overlay], &json!({ "id": 1, "tenant": "abcd" }));
+        assert!(base.validate(&json!({ "id": 1, "tenant": "abcd" })));
+        assert_eq!(vec![(ErrorCode::TooLong, "/tenant")], errors.iter().map(|error| (error.code, error.path.as_str())).collect::<Vec<_>>());
+
+        // both reject the id, it is reported once
+        let errors = Schema::validate_all_of(&[&base, &overlay], &json!({ "id": "1", "tenant": "abcd" }));
+        assert_eq!(
+            vec![(ErrorCode::WrongType, "/id"), (ErrorCode::TooLong, "/tenant")],
+            errors.iter().map(|error| (error.code, error.path.as_str())).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn validate_tree_should_nest_errors_by_path() {
+        let schema = schema(json!({
+            "type": "Dict",
+            "fields": {
+                "name": { "type": "String" },
+                "address": { "type": "Dict", "fields": { "city": { "type": "String" }, "zip": { "type": "String", "length": 6 } } },
+                "friends": { "type": "List", "element_type": { "type": "Dict", "fields": { "name": { "type": "String" } } } }
+            }
+        }));
+        assert_eq!(ErrorTree::default(), schema.validate_tree(&json!({ "name": "kilerd", "address": { "city": "Shenzhen", "zip": "518000" }, "friends": [] })));
+
+        let tree = schema.validate_tree(&json!({
+            "name": "kilerd",
+            "address": { "city": 1, "zip": "518000" },
+            "friends": [{ "name": "a" }, { "name": false }]
+        }));
+        assert!(tree.errors.is_empty());
+        assert_eq!(vec!["address", "friends"], tree.children.keys().collect::<Vec<_>>());
+
+        let address = tree.child("address").unwrap();
+        assert!(address.errors.is_empty());
+        assert_eq!(vec!["city"], address.children.keys().collect::<Vec<_>>());
+        let city = address.child("city").unwrap();
+        assert_eq!(vec![ErrorCode::WrongType], city.errors.iter().map(|error| error.code).collect::<Vec<_>>());
+        assert!(city.children.is_empty());
+
+        let friends = tree.child("friends").unwrap();
+        assert_eq!(vec!["1"], friends.children.keys().collect::<Vec<_>>());
+        let name = friends.child("1").and_then(|friend| friend.child("name")).unwrap();
+        assert_eq!("/friends/1/name", name.errors[0].path);
+        assert!(name.children.is_empty());
+    }
+
+    #[test]
+    fn heterogeneous_array_should_follow_refs_of_each_schema() {
+        let mut schemas = HashMap::new();
+        schemas.insert("user".to_owned(), serde_json::from_value::<Schema>(json!({
+            "root": { "type": "Dict", "fields": { "type": { "type": "String" }, "email": { "type": "Ref", "name": "email" } } },
+            "definitions": { "email": { "type": "String", "regex": ".+@.+" } },
+            "validators": []
+        })).unwrap());
+        let fallback = schema(json!({ "type": "Dict", "fields": { "type": { "type": "String" } } }));
+
+        assert_eq!(Ok(()), fallback.validate_heterogeneous_array("type", &schemas, &json!([{ "type": "user", "email": "kilerd@example.com" }])));
+        let elements = match fallback.validate_heterogeneous_array("type", &schemas, &json!([{ "type": "user", "email": "nope" }])) {
+            Err(HeterogeneousError::Elements(elements)) => elements,
+            other => panic!("unexpected {:?}", other),
+        };
+        assert_eq!("/0/email", elements[0].errors[0].path);
+    }
 }
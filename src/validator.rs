@@ -1,36 +1,419 @@
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::fmt;
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
-use crate::schema::{BooleanType, DictType, LiteralType, NumberType, StringType, ListType, DataType};
+use crate::error::{ErrorCode, ValidationError};
+use crate::format::{self, FormatRegistry};
+use crate::value::{canonical_cmp, canonical_eq, project};
+use crate::schema::{BooleanType, ColumnLimit, DictType, DynamicType, GraphIntegrity, LiteralType, NullType, NumberType, StringType, ListType, OneOfType, RefType, TupleType, DataType};
+
+#[derive(Debug, Clone)]
+pub struct ValidationOptions {
+    /// only check that every node has the right type, skipping regexes, lengths and other constraints
+    pub types_only: bool,
+    /// the moment time-relative constraints are evaluated against, the current time when `None`
+    pub now: Option<SystemTime>,
+    /// custom string formats, checked before the built-in ones
+    pub formats: FormatRegistry,
+    /// fail strings whose format is neither registered nor built in, instead of letting them pass
+    pub strict_formats: bool,
+    /// features whose tagged constraints apply
+    pub enabled_features: HashSet<String>,
+    /// fail a document with more nodes than this before validating any of it
+    pub max_nodes: Option<usize>,
+    /// fail a document whose compact JSON serialization is longer than this many bytes
+    pub max_serialized_bytes: Option<usize>,
+    /// stop looking once this many errors are found, [`Validator::validate`] only ever needs the first one
+    pub max_errors: Option<usize>,
+}
+
+impl ValidationOptions {
+    fn features_enabled(&self, features: &[String]) -> bool {
+        features.iter().all(|feature| self.enabled_features.contains(feature))
+    }
+
+    fn has_enough(&self, errors: &[ValidationError]) -> bool {
+        self.max_errors.is_some_and(|max_errors| errors.len() >= max_errors)
+    }
+}
+
+/// Which of several alternatives a node was validated against.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Branch {
+    /// the index of the `OneOf` variant that accepted the node
+    Variant(usize),
+    /// the `any_fields` pattern the node's key matched
+    Pattern(String),
+}
+
+/// What [`Validator::validate_outcome`] found out about a document, serializable to hand out as is.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ValidationOutcome {
+    pub valid: bool,
+    pub errors: Vec<ValidationError>,
+    /// notes on the validation itself rather than the document, like errors left uncollected past `max_errors`
+    pub warnings: Vec<String>,
+    pub stats: ValidationStats,
+    /// the branch taken at every JSON pointer that had a choice, in document order,
+    /// only recorded for a valid document
+    pub branches: Vec<(String, Branch)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ValidationStats {
+    /// every value in the document, containers included
+    pub nodes: usize,
+}
+
+impl Default for ValidationOptions {
+    fn default() -> Self {
+        ValidationOptions {
+            types_only: false,
+            now: None,
+            formats: FormatRegistry::default(),
+            strict_formats: true,
+            enabled_features: HashSet::new(),
+            max_nodes: None,
+            max_serialized_bytes: None,
+            max_errors: None,
+        }
+    }
+}
+
+/// Where a node sits in the document, built up while descending and only rendered on failure.
+#[derive(Debug, Clone, Copy)]
+pub enum Path<'a> {
+    Root,
+    Key(&'a Path<'a>, &'a str),
+    Index(&'a Path<'a>, usize),
+}
+
+impl<'a> Path<'a> {
+    pub fn key(&'a self, key: &'a str) -> Path<'a> {
+        Path::Key(self, key)
+    }
+
+    pub fn index(&'a self, index: usize) -> Path<'a> {
+        Path::Index(self, index)
+    }
+}
+
+/// renders as a JSON pointer like `/users/0/email`
+impl fmt::Display for Path<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Path::Root => Ok(()),
+            Path::Key(parent, key) => write!(f, "{}/{}", parent, key.replace('~', "~0").replace('/', "~1")),
+            Path::Index(parent, index) => write!(f, "{}/{}", parent, index),
+        }
+    }
+}
+
+fn fail(errors: &mut Vec<ValidationError>, path: &Path, code: ErrorCode, expected: impl Into<String>, actual: &Value) {
+    errors.push(ValidationError {
+        code,
+        path: path.to_string(),
+        expected: expected.into(),
+        actual: summarize(actual, 0),
+    });
+}
+
+/// A copy of the failing node cheap enough to keep, errors on a container would otherwise clone all of it.
+/// Arrays and objects keep their first entries and a note of how many were left out, deeper ones only their size.
+/// what a dict with `min_properties` expects, the common `Some(1)` of patch documents reads as a rule of its own
+pub(crate) fn at_least_properties(min: u64) -> String {
+    match min {
+        1 => "at least one field is required".to_owned(),
+        min => format!("at least {} properties", min),
+    }
+}
+
+fn summarize(node: &Value, depth: usize) -> Value {
+    const ENTRIES: usize = 8;
+    const DEPTH: usize = 2;
+    match node {
+        Value::Array(array) if depth >= DEPTH && !array.is_empty() => Value::String(format!("[{} items]", array.len())),
+        Value::Object(object) if depth >= DEPTH && !object.is_empty() => Value::String(format!("{{{} entries}}", object.len())),
+        Value::Array(array) => {
+            let mut items: Vec<Value> = array.iter().take(ENTRIES).map(|item| summarize(item, depth + 1)).collect();
+            if array.len() > ENTRIES {
+                items.push(Value::String(format!("... {} more", array.len() - ENTRIES)));
+            }
+            Value::Array(items)
+        }
+        Value::Object(object) => {
+            let mut entries: serde_json::Map<String, Value> =
+                object.iter().take(ENTRIES).map(|(key, value)| (key.clone(), summarize(value, depth + 1))).collect();
+            if object.len() > ENTRIES {
+                entries.insert("...".to_owned(), Value::String(format!("{} more", object.len() - ENTRIES)));
+            }
+            Value::Object(entries)
+        }
+        _ => node.clone(),
+    }
+}
 
 pub trait Validator {
     fn validate_type(&self, node: &Value) -> bool;
-    fn validate_meta(&self, node: &Value) -> bool;
+    /// what `validate_type` accepts, for error messages
+    fn type_name(&self) -> &'static str;
+    /// check the constraints of a node of the right type, recording every violation found
+    fn validate_meta(&self, node: &Value, options: &ValidationOptions, path: &Path, errors: &mut Vec<ValidationError>);
+    /// the features this node's constraints are tagged with, they only apply while all of them are enabled
+    fn features(&self) -> &[String] {
+        &[]
+    }
+    /// whether `null` is accepted in place of a value of this type
+    fn nullable(&self) -> bool {
+        false
+    }
     fn validate(&self, node: &Value) -> bool {
-        self.validate_type(&node) && self.validate_meta(&node)
+        self.validate_with(node, &ValidationOptions::default())
+    }
+    fn validate_with(&self, node: &Value, options: &ValidationOptions) -> bool {
+        let first_error = ValidationOptions { max_errors: Some(1), ..options.clone() };
+        self.validate_detailed_with(node, &first_error).is_ok()
+    }
+    fn validate_detailed(&self, node: &Value) -> Result<(), Vec<ValidationError>> {
+        self.validate_detailed_with(node, &ValidationOptions::default())
+    }
+    fn validate_detailed_with(&self, node: &Value, options: &ValidationOptions) -> Result<(), Vec<ValidationError>> {
+        let mut errors = vec![];
+        // both limits are checked before validating anything, so an oversized document fails cheaply
+        if let Some(max_bytes) = options.max_serialized_bytes {
+            if exceeds_serialized_size(node, max_bytes) {
+                fail(&mut errors, &Path::Root, ErrorCode::TooLarge, format!("at most {} bytes serialized", max_bytes), node);
+                return Err(errors);
+            }
+        }
+        if let Some(max_nodes) = options.max_nodes {
+            // the whole document is counted once here, not again for every subtree
+            if exceeds_node_count(node, max_nodes) {
+                fail(&mut errors, &Path::Root, ErrorCode::TooManyNodes, format!("at most {} nodes", max_nodes), node);
+                return Err(errors);
+            }
+            let options = ValidationOptions { max_nodes: None, ..options.clone() };
+            self.collect_errors(node, &options, &Path::Root, &mut errors);
+        } else {
+            self.collect_errors(node, options, &Path::Root, &mut errors);
+        }
+        if let Some(max_errors) = options.max_errors {
+            errors.truncate(max_errors);
+        }
+        if errors.is_empty() { Ok(()) } else { Err(errors) }
+    }
+    /// Validate like [`Validator::validate_detailed_with`], and for a valid document also record
+    /// which `OneOf` variant and `any_fields` pattern each node went through.
+    fn validate_outcome(&self, node: &Value, options: &ValidationOptions) -> ValidationOutcome {
+        let mut branches = vec![];
+        let errors = match self.validate_detailed_with(node, options) {
+            Ok(()) => {
+                self.collect_branches(node, options, &Path::Root, &mut branches);
+                vec![]
+            }
+            Err(errors) => errors,
+        };
+        let mut warnings = vec![];
+        if options.has_enough(&errors) {
+            warnings.push(format!("stopped after {} errors, the document may have more", errors.len()));
+        }
+        let stats = ValidationStats { nodes: node_count(node) };
+        ValidationOutcome { valid: errors.is_empty(), errors, warnings, stats, branches }
+    }
+    /// record the branches below a node already known to be valid, only types with a choice to make record any
+    fn collect_branches(&self, _node: &Value, _options: &ValidationOptions, _path: &Path, _branches: &mut Vec<(String, Branch)>) {}
+    /// a node tagged with a disabled feature is checked, along with everything below it, for types only
+    fn collect_errors(&self, node: &Value, options: &ValidationOptions, path: &Path, errors: &mut Vec<ValidationError>) {
+        collect_node_errors(self, node, options, path, errors);
+    }
+}
+
+fn collect_node_errors<V: Validator + ?Sized>(validator: &V, node: &Value, options: &ValidationOptions, path: &Path, errors: &mut Vec<ValidationError>) {
+    if (node.is_null() && validator.nullable()) || options.has_enough(errors) {
+        return;
+    }
+    if !validator.validate_type(node) {
+        fail(errors, path, ErrorCode::WrongType, validator.type_name(), node);
+        return;
+    }
+    validate_meta_with_features(validator, node, options, path, errors);
+}
+
+fn validate_meta_with_features<V: Validator + ?Sized>(validator: &V, node: &Value, options: &ValidationOptions, path: &Path, errors: &mut Vec<ValidationError>) {
+    validator.validate_meta(node, &feature_options(validator, options), path, errors)
+}
+
+/// the options a node and its children are validated with, types only while one of its features is disabled
+fn feature_options<'a, V: Validator + ?Sized>(validator: &V, options: &'a ValidationOptions) -> Cow<'a, ValidationOptions> {
+    if options.types_only || options.features_enabled(validator.features()) {
+        Cow::Borrowed(options)
+    } else {
+        Cow::Owned(ValidationOptions { types_only: true, ..options.clone() })
+    }
+}
+
+/// whether the compact JSON is longer than `limit`, counted without building the string
+/// and given up on as soon as the count goes past it
+fn exceeds_serialized_size(node: &Value, limit: usize) -> bool {
+    struct Counter {
+        count: usize,
+        limit: usize,
+    }
+
+    impl std::io::Write for Counter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.count += buf.len();
+            if self.count > self.limit {
+                return Err(std::io::Error::other("over the limit"));
+            }
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    // a `Value` always serializes, so the only error is the counter giving up
+    serde_json::to_writer(Counter { count: 0, limit }, node).is_err()
+}
+
+fn node_count(node: &Value) -> usize {
+    let mut pending = vec![node];
+    let mut count = 0;
+    while let Some(node) = pending.pop() {
+        count += 1;
+        match node {
+            Value::Array(array) => pending.extend(array.iter()),
+            Value::Object(object) => pending.extend(object.values()),
+            _ => {}
+        }
     }
+    count
+}
+
+/// stops walking as soon as the count goes past `limit`
+fn exceeds_node_count(node: &Value, limit: usize) -> bool {
+    let mut pending = vec![node];
+    let mut count = 0;
+    while let Some(node) = pending.pop() {
+        count += 1;
+        if count > limit {
+            return true;
+        }
+        match node {
+            Value::Array(array) => pending.extend(array.iter()),
+            Value::Object(object) => pending.extend(object.values()),
+            _ => {}
+        }
+    }
+    false
 }
 
 impl Validator for DataType {
     fn validate_type(&self, node: &Value) -> bool {
-        // todo nullable and optional
+        // todo optional
+        match self {
+            DataType::Number(inner) => inner.validate_type(node),
+            DataType::Dict(inner) => { inner.validate_type(node) }
+            DataType::List(inner) => { inner.validate_type(node) }
+            DataType::String(inner) => { inner.validate_type(node) }
+            DataType::Literal(inner) => { inner.validate_type(node) }
+            DataType::Boolean(inner) => { inner.validate_type(node) }
+            DataType::Tuple(inner) => { inner.validate_type(node) }
+            DataType::OneOf(inner) => { inner.validate_type(node) }
+            DataType::Null(inner) => { inner.validate_type(node) }
+            DataType::Ref(inner) => { inner.validate_type(node) }
+            DataType::Dynamic(inner) => { inner.validate_type(node) }
+        }
+    }
+
+    fn type_name(&self) -> &'static str {
+        match self {
+            DataType::Number(inner) => inner.type_name(),
+            DataType::Dict(inner) => inner.type_name(),
+            DataType::List(inner) => inner.type_name(),
+            DataType::String(inner) => inner.type_name(),
+            DataType::Literal(inner) => inner.type_name(),
+            DataType::Boolean(inner) => inner.type_name(),
+            DataType::Tuple(inner) => inner.type_name(),
+            DataType::OneOf(inner) => inner.type_name(),
+            DataType::Null(inner) => inner.type_name(),
+            DataType::Ref(inner) => inner.type_name(),
+            DataType::Dynamic(inner) => inner.type_name(),
+        }
+    }
+
+    fn validate_meta(&self, node: &Value, options: &ValidationOptions, path: &Path, errors: &mut Vec<ValidationError>) {
+        match self {
+            DataType::Number(inner) => inner.validate_meta(node, options, path, errors),
+            DataType::Dict(inner) => { inner.validate_meta(node, options, path, errors) }
+            DataType::List(inner) => { inner.validate_meta(node, options, path, errors) }
+            DataType::String(inner) => { inner.validate_meta(node, options, path, errors) }
+            DataType::Literal(inner) => { inner.validate_meta(node, options, path, errors) }
+            DataType::Boolean(inner) => { inner.validate_meta(node, options, path, errors) }
+            DataType::Tuple(inner) => { inner.validate_meta(node, options, path, errors) }
+            DataType::OneOf(inner) => { inner.validate_meta(node, options, path, errors) }
+            DataType::Null(inner) => { inner.validate_meta(node, options, path, errors) }
+            DataType::Ref(inner) => { inner.validate_meta(node, options, path, errors) }
+            DataType::Dynamic(inner) => { inner.validate_meta(node, options, path, errors) }
+        }
+    }
+
+    fn features(&self) -> &[String] {
+        match self {
+            DataType::Number(inner) => inner.features(),
+            DataType::Dict(inner) => inner.features(),
+            DataType::List(inner) => inner.features(),
+            DataType::String(inner) => inner.features(),
+            DataType::Literal(inner) => inner.features(),
+            DataType::Boolean(inner) => inner.features(),
+            DataType::Tuple(inner) => inner.features(),
+            DataType::OneOf(inner) => inner.features(),
+            DataType::Null(inner) => inner.features(),
+            DataType::Ref(inner) => inner.features(),
+            DataType::Dynamic(inner) => inner.features(),
+        }
+    }
+
+    fn nullable(&self) -> bool {
+        match self {
+            DataType::Number(inner) => inner.nullable(),
+            DataType::Dict(inner) => inner.nullable(),
+            DataType::List(inner) => inner.nullable(),
+            DataType::String(inner) => inner.nullable(),
+            DataType::Literal(inner) => inner.nullable(),
+            DataType::Boolean(inner) => inner.nullable(),
+            DataType::Tuple(inner) => inner.nullable(),
+            DataType::OneOf(inner) => inner.nullable(),
+            DataType::Null(inner) => inner.nullable(),
+            DataType::Ref(inner) => inner.nullable(),
+            DataType::Dynamic(inner) => inner.nullable(),
+        }
+    }
+
+    fn collect_errors(&self, node: &Value, options: &ValidationOptions, path: &Path, errors: &mut Vec<ValidationError>) {
         match self {
-            DataType::Number(inner) => inner.validate_type(&node),
-            DataType::Dict(inner) => { inner.validate_type(&node) }
-            DataType::List(inner) => { inner.validate_type(&node) }
-            DataType::String(inner) => { inner.validate_type(&node) }
-            DataType::Literal(inner) => { inner.validate_type(&node) }
-            DataType::Boolean(inner) => { inner.validate_type(&node) }
+            DataType::OneOf(inner) => inner.collect_errors(node, options, path, errors),
+            _ => collect_node_errors(self, node, options, path, errors),
         }
     }
 
-    fn validate_meta(&self, node: &Value) -> bool {
+    fn collect_branches(&self, node: &Value, options: &ValidationOptions, path: &Path, branches: &mut Vec<(String, Branch)>) {
         match self {
-            DataType::Number(inner) => inner.validate_meta(&node),
-            DataType::Dict(inner) => { inner.validate_meta(&node) }
-            DataType::List(inner) => { inner.validate_meta(&node) }
-            DataType::String(inner) => { inner.validate_meta(&node) }
-            DataType::Literal(inner) => { inner.validate_meta(&node) }
-            DataType::Boolean(inner) => { inner.validate_meta(&node) }
+            DataType::Dict(inner) => inner.collect_branches(node, options, path, branches),
+            DataType::List(inner) => inner.collect_branches(node, options, path, branches),
+            DataType::Tuple(inner) => inner.collect_branches(node, options, path, branches),
+            DataType::OneOf(inner) => inner.collect_branches(node, options, path, branches),
+            DataType::Ref(inner) => inner.collect_branches(node, options, path, branches),
+            DataType::Dynamic(inner) => inner.collect_branches(node, options, path, branches),
+            DataType::Number(_) | DataType::String(_) | DataType::Literal(_) | DataType::Boolean(_) | DataType::Null(_) => {}
         }
     }
 }
@@ -40,21 +423,178 @@ impl Validator for DictType {
         matches!(node, Value::Object(..))
     }
 
-    fn validate_meta(&self, node: &Value) -> bool {
+    fn type_name(&self) -> &'static str {
+        "an object"
+    }
+
+    fn features(&self) -> &[String] {
+        &self.features
+    }
+
+    fn nullable(&self) -> bool {
+        self.nullable
+    }
+
+    fn validate_meta(&self, node: &Value, options: &ValidationOptions, path: &Path, errors: &mut Vec<ValidationError>) {
         let object = match node {
             Value::Object(inner) => inner,
             _ => unreachable!()
         };
-        
+
+        // checked before walking the keys, so an oversized map fails without touching its entries
+        if let Some(max_properties) = self.max_properties.filter(|_| !options.types_only) {
+            if object.len() as u64 > max_properties {
+                fail(errors, path, ErrorCode::TooManyProperties, format!("at most {} properties", max_properties), node);
+                return;
+            }
+        }
+        if let Some(min_properties) = self.min_properties.filter(|_| !options.types_only) {
+            if (object.len() as u64) < min_properties {
+                fail(errors, path, ErrorCode::TooFewProperties, at_least_properties(min_properties), node);
+            }
+        }
+        if self.integer_key_sequence && !options.types_only {
+            // keys are unique, so n canonical indices below n are exactly 0..n
+            let is_index = |key: &String| {
+                key.parse::<usize>()
+                    .is_ok_and(|index| index < object.len() && index.to_string() == *key)
+            };
+            if !object.keys().all(is_index) {
+                fail(errors, path, ErrorCode::NonSequentialKeys, "keys counting up from 0", node);
+            }
+        }
+        if self.unique_values && !options.types_only {
+            let values: Vec<&Value> = object.values().collect();
+            let duplicated = values
+                .iter()
+                .enumerate()
+                .any(|(index, value)| values[index + 1..].iter().any(|other| canonical_eq(value, other)));
+            if duplicated {
+                fail(errors, path, ErrorCode::DuplicateValue, "unique values", node);
+            }
+        }
+        if !self.shapes.is_empty() && !options.types_only {
+            let matches = |shape: &&Vec<String>| {
+                shape.len() == object.len() && shape.iter().all(|key| object.contains_key(key))
+            };
+            if self.shapes.iter().filter(matches).count() != 1 {
+                fail(errors, path, ErrorCode::NoMatchingShape, "the keys of exactly one shape", node);
+            }
+        }
+        if let Some(length_of) = self.length_of.as_ref().filter(|_| !options.types_only) {
+            for (count_key, array_key) in length_of {
+                // a missing side is left to the required-field check
+                if let (Some(count), Some(array)) = (object.get(count_key), object.get(array_key)) {
+                    // a count written as `2.0` is still the integer 2
+                    let matches = match (count.as_number(), array.as_array()) {
+                        (Some(count), Some(array)) => is_integral(count) && count.as_f64() == Some(array.len() as f64),
+                        _ => false,
+                    };
+                    if !matches {
+                        fail(errors, &path.key(count_key), ErrorCode::LengthMismatch, format!("the length of `{}`", array_key), count);
+                    }
+                }
+            }
+        }
+        if !options.types_only {
+            for group in self.parallel_arrays.iter() {
+                // measured against the first array present, a missing or mistyped one is reported by its own field
+                let mut arrays = group.iter().filter_map(|key| object.get(key).and_then(Value::as_array).map(|array| (key, array)));
+                if let Some((first, expected)) = arrays.next() {
+                    for (key, array) in arrays.filter(|(_, array)| array.len() != expected.len()) {
+                        fail(errors, &path.key(key), ErrorCode::LengthMismatch, format!("{} items, as many as `{}`", expected.len(), first), &Value::from(array.len()));
+                    }
+                }
+            }
+        }
+        if let Some(conflicts) = self.conflicts.as_ref().filter(|_| !options.types_only) {
+            for (key, conflicting) in conflicts {
+                if !object.contains_key(key) {
+                    continue;
+                }
+                for field in conflicting.iter().filter(|field| object.contains_key(*field)) {
+                    fail(errors, &path.key(field), ErrorCode::ConflictingField, format!("no `{}` alongside `{}`", field, key), &object[field]);
+                }
+            }
+        }
+
+        if let Some(graph) = self.graph_integrity.as_ref().filter(|_| !options.types_only) {
+            for (key, value) in object.iter() {
+                let edges_path = path.key(key);
+                let edges_path = match &graph.edges {
+                    Some(field) => edges_path.key(field),
+                    None => edges_path,
+                };
+                for (index, edge) in graph.edges(value) {
+                    if !object.contains_key(edge) {
+                        fail(errors, &edges_path.index(index), ErrorCode::DanglingReference, "a declared node", &Value::String(edge.to_owned()));
+                    }
+                }
+            }
+            if graph.acyclic {
+                if let Some(node) = find_cycle(object, graph) {
+                    fail(errors, &path.key(node), ErrorCode::Cycle, format!("no dependency cycle through `{}`", node), &object[node]);
+                }
+            }
+        }
+        if let Some(property_names) = &self.property_names {
+            for key in object.keys() {
+                property_names.collect_errors(&Value::String(key.clone()), options, &path.key(key), errors);
+            }
+        }
         for (key, value) in object.iter() {
-            let contains_normal_field = self.fields.contains_key(key);
-            if contains_normal_field {
-                // todo DateType::Validate
-            } else {
-                return false;
+            if options.has_enough(errors) {
+                return;
+            }
+            let path = path.key(key);
+            match self.fields.get(key) {
+                Some(field) => field.collect_errors(value, options, &path, errors),
+                None => {
+                    let mut matched = false;
+                    for (pattern, field) in self.any_fields.iter().flatten() {
+                        if pattern.is_match(key) {
+                            matched = true;
+                            field.collect_errors(value, options, &path, errors);
+                        }
+                    }
+                    if !matched {
+                        match &self.others {
+                            Some(others) => others.collect_errors(value, options, &path, errors),
+                            None => fail(errors, &path, ErrorCode::UnknownField, "no undeclared field", value),
+                        }
+                    }
+                }
+            }
+        }
+        for (key, field) in self.fields.iter() {
+            if !(self.all_optional || field.is_optional() || object.contains_key(key)) {
+                fail(errors, &path.key(key), ErrorCode::MissingField, format!("required field `{}`", key), &Value::Null);
             }
+        }
+    }
+
+    fn collect_branches(&self, node: &Value, options: &ValidationOptions, path: &Path, branches: &mut Vec<(String, Branch)>) {
+        let object = match node {
+            Value::Object(object) => object,
+            _ => return,
         };
-        true
+        let options = feature_options(self, options);
+        for (key, value) in object.iter() {
+            let path = path.key(key);
+            if let Some(field) = self.fields.get(key) {
+                field.collect_branches(value, &options, &path, branches);
+                continue;
+            }
+            let mut matched = false;
+            for (pattern, field) in self.any_fields.iter().flatten().filter(|(pattern, _)| pattern.is_match(key)) {
+                matched = true;
+                branches.push((path.to_string(), Branch::Pattern(pattern.as_str().to_owned())));
+                field.collect_branches(value, &options, &path, branches);
+            }
+            if let Some(others) = self.others.as_ref().filter(|_| !matched) {
+                others.collect_branches(value, &options, &path, branches);
+            }
+        }
     }
 }
 
@@ -63,23 +603,106 @@ impl Validator for ListType {
         matches!(node, Value::Array(..))
     }
 
-    fn validate_meta(&self, node: &Value) -> bool {
+    fn type_name(&self) -> &'static str {
+        "an array"
+    }
+
+    fn features(&self) -> &[String] {
+        &self.features
+    }
+
+    fn nullable(&self) -> bool {
+        self.nullable
+    }
+
+    fn validate_meta(&self, node: &Value, options: &ValidationOptions, path: &Path, errors: &mut Vec<ValidationError>) {
         let array = match node {
             Value::Array(inner) => inner,
             _ => unreachable!()
         };
 
-        if let Some(limit) = self.limit {
-            if array.len() as u64 > limit {
-                return false;
+        if let Some(max_items) = self.max_items.filter(|_| !options.types_only) {
+            if array.len() as u64 > max_items {
+                fail(errors, path, ErrorCode::TooManyItems, format!("at most {} items", max_items), node);
             }
         }
-        for item in array {
-            if !self.element_type.validate(item) {
-                return false;
+        if let Some(exact_set) = self.exact_set.as_ref().filter(|_| !options.types_only) {
+            // compared as multisets, so `[1, 1, 2]` takes exactly two ones and a two
+            let count = |list: &[Value], item: &Value| list.iter().filter(|other| canonical_eq(other, item)).count();
+            if array.len() != exact_set.len() || !exact_set.iter().all(|item| count(array, item) == count(exact_set, item)) {
+                fail(errors, path, ErrorCode::NotExactSet, format!("a permutation of {}", Value::Array(exact_set.clone())), node);
             }
         }
-        true
+        if let Some(relation) = self.element_relation.as_ref().filter(|_| !options.types_only) {
+            for (index, pair) in array.windows(2).enumerate() {
+                let ordering = match (pair[0].get(&relation.previous), pair[1].get(&relation.current)) {
+                    (Some(previous), Some(current)) => canonical_cmp(previous, current),
+                    _ => None,
+                };
+                if !ordering.is_some_and(|ordering| relation.operator.holds(ordering)) {
+                    let expected = format!("previous.{} {} current.{}", relation.previous, relation.operator, relation.current);
+                    fail(errors, &path.index(index + 1), ErrorCode::RelationViolated, expected, &pair[1]);
+                }
+            }
+        }
+        if let Some(key_path) = self.strictly_sorted_by.as_ref().filter(|_| !options.types_only) {
+            for (index, pair) in array.windows(2).enumerate() {
+                let ordering = match (project(&pair[0], key_path), project(&pair[1], key_path)) {
+                    (Some(previous), Some(current)) => canonical_cmp(previous, current),
+                    _ => None,
+                };
+                if ordering != Some(Ordering::Less) {
+                    let expected = format!("a `{}` greater than the previous item's", key_path.join("."));
+                    fail(errors, &path.index(index + 1), ErrorCode::NotStrictlySorted, expected, &pair[1]);
+                }
+            }
+        }
+        if let Some(ignored) = self.unique_ignoring.as_ref().filter(|_| !options.types_only) {
+            let stripped: Vec<Value> = array
+                .iter()
+                .map(|item| match item {
+                    Value::Object(object) => {
+                        let kept = object.iter().filter(|(key, _)| !ignored.contains(key));
+                        Value::Object(kept.map(|(key, value)| (key.clone(), value.clone())).collect())
+                    }
+                    _ => item.clone(),
+                })
+                .collect();
+            for (index, item) in stripped.iter().enumerate() {
+                if stripped[..index].iter().any(|other| canonical_eq(item, other)) {
+                    let expected = format!("an item unique apart from {}", ignored.join(", "));
+                    fail(errors, &path.index(index), ErrorCode::DuplicateItem, expected, &array[index]);
+                }
+            }
+        }
+        if let Some(distribution) = self.probability_distribution.filter(|_| !options.types_only) {
+            // anything not a number is left to the element type
+            let probabilities = array.iter().enumerate().filter_map(|(index, item)| Some((index, item.as_f64()?)));
+            let mut sum = 0.0;
+            for (index, probability) in probabilities {
+                if probability < 0.0 {
+                    fail(errors, &path.index(index), ErrorCode::BelowMinimum, "a non-negative probability", &array[index]);
+                }
+                sum += probability;
+            }
+            if (sum - 1.0).abs() > distribution.tolerance {
+                let expected = format!("probabilities adding up to 1 within {}, not {}", distribution.tolerance, sum);
+                fail(errors, path, ErrorCode::NotADistribution, expected, node);
+            }
+        }
+        for (index, item) in array.iter().enumerate() {
+            if options.has_enough(errors) {
+                return;
+            }
+            self.element_type.collect_errors(item, options, &path.index(index), errors);
+        }
+    }
+
+    fn collect_branches(&self, node: &Value, options: &ValidationOptions, path: &Path, branches: &mut Vec<(String, Branch)>) {
+        let options = feature_options(self, options);
+        for (index, item) in node.as_array().into_iter().flatten().enumerate() {
+            self.element_type.collect_branches(item, &options, &path.index(index), branches);
+        }
     }
 }
 
@@ -88,12 +711,26 @@ impl Validator for LiteralType {
         matches!(node, Value::String(..))
     }
 
-    fn validate_meta(&self, node: &Value) -> bool {
+    fn type_name(&self) -> &'static str {
+        "a string"
+    }
+
+    fn features(&self) -> &[String] {
+        &self.features
+    }
+
+    fn nullable(&self) -> bool {
+        self.nullable
+    }
+
+    fn validate_meta(&self, node: &Value, options: &ValidationOptions, path: &Path, errors: &mut Vec<ValidationError>) {
         let inner = match node {
             Value::String(inner) => inner,
             _ => unreachable!()
         };
-        self.candidate.contains(inner)
+        if !options.types_only && !self.candidate.contains(inner) {
+            fail(errors, path, ErrorCode::NotACandidate, format!("one of {}", self.candidate.join(", ")), node);
+        }
     }
 }
 
@@ -102,18 +739,111 @@ impl Validator for StringType {
         matches!(node, Value::String(..))
     }
 
-    fn validate_meta(&self, node: &Value) -> bool {
+    fn type_name(&self) -> &'static str {
+        "a string"
+    }
+
+    fn features(&self) -> &[String] {
+        &self.features
+    }
+
+    fn nullable(&self) -> bool {
+        self.nullable
+    }
+
+    fn validate_meta(&self, node: &Value, options: &ValidationOptions, path: &Path, errors: &mut Vec<ValidationError>) {
         let inner = match node {
             Value::String(inner) => inner,
             _ => unreachable!()
         };
+        if options.types_only {
+            return;
+        }
         if let Some(limit) = &self.length {
-            if inner.len() as u64 > *limit { return false; }
+            if self.length_unit.measure(inner) > *limit {
+                fail(errors, path, ErrorCode::TooLong, format!("at most {} {}", limit, self.length_unit.name()), node);
+            }
+        }
+        if let Some(min_length) = self.min_length {
+            if self.length_unit.measure(inner) < min_length {
+                fail(errors, path, ErrorCode::TooShort, format!("at least {} {}", min_length, self.length_unit.name()), node);
+            }
+        }
+        if self.no_surrounding_whitespace && inner.trim() != inner {
+            fail(errors, path, ErrorCode::SurroundingWhitespace, "no surrounding whitespace", node);
         }
         if let Some(reg) = &self.regex {
-            let result = regex::Regex::new(&format!("^{}$", reg)).unwrap();
-            if !result.is_match(inner) {
-                return false;
+            if !reg.is_match(inner) {
+                fail(errors, path, ErrorCode::PatternMismatch, format!("a match for /{}/", reg.as_str()), node);
+            }
+        }
+        if let Some(name) = &self.format {
+            if !self.validate_format(name, inner, options) {
+                fail(errors, path, ErrorCode::InvalidFormat, format!("a valid {}", name), node);
+            }
+        }
+        if let Some(column) = &self.column {
+            if !ColumnLimit::parse(column).is_some_and(|limit| limit.fits(inner)) {
+                fail(errors, path, ErrorCode::ExceedsColumn, format!("a value fitting {}", column), node);
+            }
+        }
+        if let Some(pattern) = &self.strftime {
+            if !format::matches_strftime(inner, pattern) {
+                fail(errors, path, ErrorCode::StrftimeMismatch, format!("a time formatted as {}", pattern), node);
+            }
+        }
+    }
+}
+
+impl StringType {
+    fn validate_format(&self, name: &str, inner: &str, options: &ValidationOptions) -> bool {
+        if let Some(check) = options.formats.get(name) {
+            return check(inner);
+        }
+        match name {
+            format::DATE_TIME => match format::parse_date_time(inner) {
+                Some(date_time) => {
+                    if self.require_offset && date_time.offset.is_none() {
+                        return false;
+                    }
+                    if self.require_utc && date_time.offset != Some(0) {
+                        return false;
+                    }
+                    self.validate_time_bounds(date_time.timestamp, options)
+                }
+                None => false,
+            },
+            format::DECIMAL => {
+                let decimal_separator = self.decimal_separator.unwrap_or('.');
+                format::parse_decimal(inner, self.grouping, decimal_separator).is_some()
+            }
+            format::HEX | format::BINARY => {
+                let radix = if name == format::HEX { 16 } else { 2 };
+                match format::significant_bits(inner, radix) {
+                    Some(bits) => self.bit_width.is_none_or(|width| bits <= width),
+                    None => false,
+                }
+            }
+            format::JSON_POINTER => format::is_json_pointer(inner),
+            format::JSONPATH => format::is_jsonpath(inner),
+            format::UUID => format::uuid_version(inner)
+                .is_some_and(|version| self.uuid_version.is_none_or(|required| version == required)),
+            _ => !options.strict_formats,
+        }
+    }
+
+    fn validate_time_bounds(&self, timestamp: i64, options: &ValidationOptions) -> bool {
+        let now = format::unix_timestamp(options.now.unwrap_or_else(SystemTime::now));
+        if let Some(after) = &self.after {
+            match format::parse_time_bound(after, now) {
+                Some(bound) if timestamp > bound => {}
+                _ => return false,
+            }
+        }
+        if let Some(before) = &self.before {
+            match format::parse_time_bound(before, now) {
+                Some(bound) if timestamp < bound => {}
+                _ => return false,
             }
         }
         true
@@ -125,59 +855,356 @@ impl Validator for NumberType {
         matches!(node, Value::Number(..))
     }
 
-    fn validate_meta(&self, node: &Value) -> bool {
-        true
+    fn type_name(&self) -> &'static str {
+        "a number"
     }
-}
 
+    fn features(&self) -> &[String] {
+        &self.features
+    }
 
-impl Validator for BooleanType {
-    fn validate_type(&self, node: &Value) -> bool {
-        matches!(node, Value::Bool(..))
+    fn nullable(&self) -> bool {
+        self.nullable
     }
 
-    fn validate_meta(&self, node: &Value) -> bool {
-        self.validate_type(&node)
+    fn validate_meta(&self, node: &Value, options: &ValidationOptions, path: &Path, errors: &mut Vec<ValidationError>) {
+        let number = match node {
+            Value::Number(inner) => inner,
+            _ => unreachable!()
+        };
+        if options.types_only {
+            return;
+        }
+        if self.safe_integer && !is_safe_integer(number) {
+            fail(errors, path, ErrorCode::UnsafeInteger, "an integer within ±(2^53 - 1)", node);
+        }
+        if self.integer && !is_integral(number) {
+            fail(errors, path, ErrorCode::NotAnInteger, "an integer", node);
+        }
+        if self.fits_f32 && !number.as_f64().is_some_and(|value| value as f32 as f64 == value) {
+            fail(errors, path, ErrorCode::NotF32, "a number exactly representable as f32", node);
+        }
+        if let Some(format) = self.format {
+            if !number.as_f64().is_some_and(|value| format.contains(value)) {
+                fail(errors, path, ErrorCode::InvalidFormat, format!("a valid {}", format.name()), node);
+            }
+        }
+        let value = number.as_f64();
+        if let Some(minimum) = self.minimum {
+            let above = |value: f64| if self.exclusive_minimum { value > minimum } else { value >= minimum };
+            if !value.is_some_and(above) {
+                let bound = if self.exclusive_minimum { "greater than" } else { "at least" };
+                fail(errors, path, ErrorCode::BelowMinimum, format!("a number {} {}", bound, minimum), node);
+            }
+        }
+        if let Some(maximum) = self.maximum {
+            let below = |value: f64| if self.exclusive_maximum { value < maximum } else { value <= maximum };
+            if !value.is_some_and(below) {
+                let bound = if self.exclusive_maximum { "less than" } else { "at most" };
+                fail(errors, path, ErrorCode::AboveMaximum, format!("a number {} {}", bound, maximum), node);
+            }
+        }
+        if let Some(grid) = self.grid {
+            if !value.is_some_and(|value| grid.contains(value)) {
+                let expected = format!("a multiple of {} offset by {}", grid.step, grid.offset);
+                fail(errors, path, ErrorCode::OffGrid, expected, node);
+            }
+        }
+        if let Some(candidate) = &self.candidate {
+            if !value.is_some_and(|value| candidate.contains(&value)) {
+                let candidate: Vec<String> = candidate.iter().map(f64::to_string).collect();
+                fail(errors, path, ErrorCode::NotACandidate, format!("one of {}", candidate.join(", ")), node);
+            }
+        }
     }
 }
 
+/// a node on some cycle of the graph, found by depth-first search from every node in key order,
+/// with an explicit stack so that long dependency chains do not overflow the call stack
+fn find_cycle<'a>(object: &'a serde_json::Map<String, Value>, graph: &GraphIntegrity) -> Option<&'a str> {
+    // dangling edges are reported on their own and lead nowhere
+    let edges = |node: &str| -> std::vec::IntoIter<&'a str> {
+        let edges = graph.edges(&object[node]).into_iter();
+        let declared = edges.filter_map(|(_, edge)| object.get_key_value(edge).map(|(edge, _)| edge.as_str()));
+        declared.collect::<Vec<_>>().into_iter()
+    };
 
-#[cfg(test)]
-mod tests {
-    use std::collections::HashMap;
+    let mut on_stack = HashSet::new();
+    let mut finished = HashSet::new();
+    for start in object.keys() {
+        if finished.contains(start.as_str()) {
+            continue;
+        }
+        on_stack.insert(start.as_str());
+        let mut stack = vec![(start.as_str(), edges(start))];
+        while let Some((node, pending)) = stack.last_mut() {
+            match pending.next() {
+                Some(edge) if finished.contains(edge) => {}
+                Some(edge) => {
+                    if !on_stack.insert(edge) {
+                        return Some(edge);
+                    }
+                    stack.push((edge, edges(edge)));
+                }
+                None => {
+                    on_stack.remove(*node);
+                    finished.insert(*node);
+                    stack.pop();
+                }
+            }
+        }
+    }
+    None
+}
 
-    use serde_json::{Number, Value};
-    use serde_json::json;
+const MAX_SAFE_INTEGER: u64 = (1 << 53) - 1;
 
-    use crate::schema::{BooleanType, DataType, DictType, LiteralType, NumberType, StringType, ListType};
-    use crate::validator::Validator;
+/// a float like `2.0` has no fractional part and counts as well
+fn is_integral(number: &serde_json::Number) -> bool {
+    number.is_i64() || number.is_u64() || number.as_f64().is_some_and(|value| value.fract() == 0.0)
+}
 
-    fn basic_validate(validator: &dyn Validator, content: impl Into<String>) -> bool {
-        let node: Value = serde_json::from_str(content.into().as_str()).unwrap();
-        validator.validate(&node)
+/// non-integral numbers are not integers at all, so they are left alone
+fn is_safe_integer(number: &serde_json::Number) -> bool {
+    if let Some(integer) = number.as_i64() {
+        return integer.unsigned_abs() <= MAX_SAFE_INTEGER;
+    }
+    if number.is_u64() {
+        return false;
     }
+    let float = number.as_f64().unwrap();
+    float.fract() != 0.0 || float.abs() <= MAX_SAFE_INTEGER as f64
+}
 
-    #[test]
-    fn test_bool_type() {
-        let validator = BooleanType { optional: false, nullable: false };
-        assert_eq!(true, validator.validate_type(&Value::Bool(true)));
-        assert_eq!(true, validator.validate_type(&Value::Bool(false)));
-        assert_eq!(false, validator.validate_type(&Value::Null));
-        assert_eq!(false, validator.validate_type(&Value::String("it".to_owned())));
-        assert_eq!(false, validator.validate_type(&json!([])));
-        assert_eq!(false, validator.validate_type(&Value::Number(Number::from(1i8))));
-        assert_eq!(false, validator.validate_type(&json!({ "an": "object" })));
+impl Validator for TupleType {
+    fn validate_type(&self, node: &Value) -> bool {
+        matches!(node, Value::Array(..))
     }
 
-    #[test]
-    fn test_dict_type() {
-        let validator = DictType {
-            optional: false,
-            nullable: false,
-            fields: Default::default(),
-            any_fields: None,
-            others: None,
+    fn type_name(&self) -> &'static str {
+        "an array"
+    }
+
+    fn features(&self) -> &[String] {
+        &self.features
+    }
+
+    fn nullable(&self) -> bool {
+        self.nullable
+    }
+
+    fn validate_meta(&self, node: &Value, options: &ValidationOptions, path: &Path, errors: &mut Vec<ValidationError>) {
+        let array = match node {
+            Value::Array(inner) => inner,
+            _ => unreachable!()
         };
+
+        let arity = self.elements.len();
+        if array.len() < arity || (self.rest.is_none() && array.len() > arity) {
+            let expected = match self.rest {
+                Some(_) => format!("at least {} elements", arity),
+                None => format!("exactly {} elements", arity),
+            };
+            fail(errors, path, ErrorCode::WrongLength, expected, node);
+        }
+        for (index, item) in array.iter().enumerate() {
+            if let Some(element) = self.element(index) {
+                element.collect_errors(item, options, &path.index(index), errors);
+            }
+        }
+    }
+
+    fn collect_branches(&self, node: &Value, options: &ValidationOptions, path: &Path, branches: &mut Vec<(String, Branch)>) {
+        let options = feature_options(self, options);
+        for (index, item) in node.as_array().into_iter().flatten().enumerate() {
+            if let Some(element) = self.element(index) {
+                element.collect_branches(item, &options, &path.index(index), branches);
+            }
+        }
+    }
+}
+
+impl Validator for OneOfType {
+    fn validate_type(&self, node: &Value) -> bool {
+        self.variants.iter().any(|variant| (node.is_null() && variant.nullable()) || variant.validate_type(node))
+    }
+
+    fn type_name(&self) -> &'static str {
+        "a value one of the variants accepts"
+    }
+
+    fn features(&self) -> &[String] {
+        &self.features
+    }
+
+    fn nullable(&self) -> bool {
+        self.nullable
+    }
+
+    /// when no variant matches, every variant's errors are reported after a summary
+    fn validate_meta(&self, node: &Value, options: &ValidationOptions, path: &Path, errors: &mut Vec<ValidationError>) {
+        let mut failures = vec![];
+        for variant in self.variants.iter() {
+            let mut variant_errors = vec![];
+            variant.collect_errors(node, options, path, &mut variant_errors);
+            if variant_errors.is_empty() {
+                return;
+            }
+            failures.extend(variant_errors);
+        }
+        fail(errors, path, ErrorCode::NoMatchingVariant, format!("a match for one of {} variants", self.variants.len()), node);
+        errors.extend(failures);
+    }
+
+    /// a value of the wrong type is reported variant by variant too, not as a single `WrongType`
+    fn collect_errors(&self, node: &Value, options: &ValidationOptions, path: &Path, errors: &mut Vec<ValidationError>) {
+        if (node.is_null() && self.nullable) || options.has_enough(errors) {
+            return;
+        }
+        validate_meta_with_features(self, node, options, path, errors);
+    }
+
+    /// the first variant without errors, as validation picks it
+    fn collect_branches(&self, node: &Value, options: &ValidationOptions, path: &Path, branches: &mut Vec<(String, Branch)>) {
+        if node.is_null() && self.nullable {
+            return;
+        }
+        let options = feature_options(self, options);
+        let accepts = |variant: &&DataType| {
+            let mut errors = vec![];
+            variant.collect_errors(node, &options, path, &mut errors);
+            errors.is_empty()
+        };
+        if let Some((index, variant)) = self.variants.iter().enumerate().find(|(_, variant)| accepts(variant)) {
+            branches.push((path.to_string(), Branch::Variant(index)));
+            variant.collect_branches(node, &options, path, branches);
+        }
+    }
+}
+
+impl Validator for BooleanType {
+    fn validate_type(&self, node: &Value) -> bool {
+        matches!(node, Value::Bool(..))
+    }
+
+    fn type_name(&self) -> &'static str {
+        "a boolean"
+    }
+
+    fn features(&self) -> &[String] {
+        &self.features
+    }
+
+    fn nullable(&self) -> bool {
+        self.nullable
+    }
+
+    fn validate_meta(&self, _node: &Value, _options: &ValidationOptions, _path: &Path, _errors: &mut Vec<ValidationError>) {}
+}
+
+impl Validator for NullType {
+    fn validate_type(&self, node: &Value) -> bool {
+        node.is_null()
+    }
+
+    fn type_name(&self) -> &'static str {
+        "null"
+    }
+
+    fn features(&self) -> &[String] {
+        &self.features
+    }
+
+    fn validate_meta(&self, _node: &Value, _options: &ValidationOptions, _path: &Path, _errors: &mut Vec<ValidationError>) {}
+}
+
+impl Validator for RefType {
+    /// whatever the definition accepts, a null it would take included
+    fn validate_type(&self, node: &Value) -> bool {
+        self.target().is_some_and(|target| (node.is_null() && target.nullable()) || target.validate_type(node))
+    }
+
+    fn type_name(&self) -> &'static str {
+        self.target().map_or("a value of a resolved definition", |target| target.type_name())
+    }
+
+    fn features(&self) -> &[String] {
+        &[]
+    }
+
+    fn nullable(&self) -> bool {
+        self.nullable
+    }
+
+    fn validate_meta(&self, node: &Value, options: &ValidationOptions, path: &Path, errors: &mut Vec<ValidationError>) {
+        if let Some(target) = self.target() {
+            target.collect_errors(node, options, path, errors);
+        }
+    }
+
+    fn collect_branches(&self, node: &Value, options: &ValidationOptions, path: &Path, branches: &mut Vec<(String, Branch)>) {
+        if let Some(target) = self.target().filter(|_| !(node.is_null() && self.nullable)) {
+            target.collect_branches(node, options, path, branches);
+        }
+    }
+}
+
+impl Validator for DynamicType {
+    fn validate_type(&self, node: &Value) -> bool {
+        self.select(node).is_some_and(|variant| variant.validate_type(node))
+    }
+
+    fn type_name(&self) -> &'static str {
+        "a value one of the variants accepts"
+    }
+
+    /// the chosen variant's own features are respected by validating it in full
+    fn validate_meta(&self, node: &Value, options: &ValidationOptions, path: &Path, errors: &mut Vec<ValidationError>) {
+        if let Some(variant) = self.select(node) {
+            variant.collect_errors(node, options, path, errors);
+        }
+    }
+
+    fn collect_branches(&self, node: &Value, options: &ValidationOptions, path: &Path, branches: &mut Vec<(String, Branch)>) {
+        if let Some(variant) = self.select(node) {
+            variant.collect_branches(node, options, path, branches);
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
+mod tests {
+    use std::collections::HashMap;
+
+    use serde_json::{Number, Value};
+    use serde_json::json;
+
+    use crate::builder::{list_of, literal, string};
+    use crate::error::{ErrorCode, ValidationError};
+    use crate::schema::{BooleanType, Comparison, NullType, DataType, DictType, DynamicType, ElementRelation, Grid, LengthUnit, LiteralType, NumberFormat, NumberType, StringType, ListType};
+    use crate::validator::{Branch, ValidationOptions, Validator};
+
+    fn basic_validate(validator: &dyn Validator, content: impl Into<String>) -> bool {
+        let node: Value = serde_json::from_str(content.into().as_str()).unwrap();
+        validator.validate(&node)
+    }
+
+    #[test]
+    fn test_bool_type() {
+        let validator = BooleanType::default();
+        assert_eq!(true, validator.validate_type(&Value::Bool(true)));
+        assert_eq!(true, validator.validate_type(&Value::Bool(false)));
+        assert_eq!(false, validator.validate_type(&Value::Null));
+        assert_eq!(false, validator.validate_type(&Value::String("it".to_owned())));
+        assert_eq!(false, validator.validate_type(&json!([])));
+        assert_eq!(false, validator.validate_type(&Value::Number(Number::from(1i8))));
+        assert_eq!(false, validator.validate_type(&json!({ "an": "object" })));
+    }
+
+    #[test]
+    fn test_dict_type() {
+        let validator = DictType::default();
         assert_eq!(false, validator.validate_type(&Value::Bool(true)));
         assert_eq!(false, validator.validate_type(&Value::Bool(false)));
         assert_eq!(false, validator.validate_type(&Value::Null));
@@ -189,11 +1216,7 @@ mod tests {
 
     #[test]
     fn test_literal_type() {
-        let validator = LiteralType {
-            optional: false,
-            nullable: false,
-            candidate: vec![],
-        };
+        let validator = LiteralType::default();
         assert_eq!(false, validator.validate_type(&Value::Bool(true)));
         assert_eq!(false, validator.validate_type(&Value::Bool(false)));
         assert_eq!(false, validator.validate_type(&Value::Null));
@@ -205,12 +1228,7 @@ mod tests {
 
     #[test]
     fn test_string_type() {
-        let validator = StringType {
-            optional: false,
-            nullable: false,
-            length: None,
-            regex: None,
-        };
+        let validator = StringType::default();
         assert_eq!(false, validator.validate_type(&Value::Bool(true)));
         assert_eq!(false, validator.validate_type(&Value::Bool(false)));
         assert_eq!(false, validator.validate_type(&Value::Null));
@@ -222,10 +1240,7 @@ mod tests {
 
     #[test]
     fn test_number_type() {
-        let validator = NumberType {
-            optional: false,
-            nullable: false,
-        };
+        let validator = NumberType::default();
         assert_eq!(false, validator.validate_type(&Value::Bool(true)));
         assert_eq!(false, validator.validate_type(&Value::Bool(false)));
         assert_eq!(false, validator.validate_type(&Value::Null));
@@ -237,12 +1252,7 @@ mod tests {
 
     #[test]
     fn test_list_type() {
-        let validator = ListType {
-            optional: false,
-            nullable: false,
-            element_type: DataType::Boolean(Box::new(BooleanType { optional: false, nullable: false })),
-            limit: None,
-        };
+        let validator = list_of(BooleanType::default());
         assert_eq!(false, validator.validate_type(&Value::Bool(true)));
         assert_eq!(false, validator.validate_type(&Value::Bool(false)));
         assert_eq!(false, validator.validate_type(&Value::Null));
@@ -255,13 +1265,10 @@ mod tests {
     #[test]
     fn dict_type_should_have_one_field() {
         let mut map = HashMap::new();
-        map.insert("a".to_owned(), DataType::Boolean(Box::new(BooleanType { optional: false, nullable: false })));
+        map.insert("a".to_owned(), BooleanType::default().into());
         let validator = DictType {
-            optional: false,
-            nullable: false,
             fields: map,
-            any_fields: None,
-            others: None,
+            ..Default::default()
         };
 
         assert_eq!(true, basic_validate(&validator, r#" {"a": true} "#));
@@ -271,9 +1278,8 @@ mod tests {
     #[test]
     fn literal_type_should_be_in_candidate() {
         let validator = LiteralType {
-            optional: false,
-            nullable: false,
             candidate: vec!["a".to_owned(), "b".to_owned(), "c".to_owned()],
+            ..Default::default()
         };
 
         assert_eq!(true, validator.validate(&Value::String("a".to_owned())));
@@ -284,12 +1290,7 @@ mod tests {
 
     #[test]
     fn string_type_should_limit_with_length() {
-        let string_type = StringType {
-            optional: false,
-            nullable: false,
-            length: Some(10),
-            regex: None,
-        };
+        let string_type = StringType { length: Some(10), ..Default::default() };
         assert_eq!(true, string_type.validate(&Value::String("1".to_owned())));
         assert_eq!(true, string_type.validate(&Value::String("".to_owned())));
         assert_eq!(true, string_type.validate(&Value::String("1234567890".to_owned())));
@@ -300,28 +1301,24 @@ mod tests {
 
     #[test]
     fn string_type_should_match_by_regex() {
-        let string_type = StringType {
-            optional: false,
-            nullable: false,
-            length: None,
-            regex: Some("[0-9]+".to_owned()),
-        };
+        let string_type = StringType { regex: Some("[0-9]+".into()), ..Default::default() };
         assert_eq!(true, string_type.validate(&Value::String("1".to_owned())));
         assert_eq!(false, string_type.validate(&Value::String("".to_owned())));
         assert_eq!(true, string_type.validate(&Value::String("1234567890".to_owned())));
         assert_eq!(false, string_type.validate(&Value::String("emoji👍123".to_owned())));
         assert_eq!(false, string_type.validate(&Value::String("utf8中文".to_owned())));
         assert_eq!(true, string_type.validate(&Value::String("12345678901".to_owned())));
+
+        let alternation = StringType { regex: Some("a|b".into()), ..Default::default() };
+        assert_eq!(true, alternation.validate(&json!("a")));
+        assert_eq!(true, alternation.validate(&json!("b")));
+        assert_eq!(false, alternation.validate(&json!("axxxxx")));
+        assert_eq!(false, alternation.validate(&json!("xxxxxb")));
     }
 
     #[test]
     fn list_type_should_validate_element_type() {
-        let validator = ListType {
-            optional: false,
-            nullable: false,
-            element_type: DataType::Boolean(Box::new(BooleanType { optional: false, nullable: false })),
-            limit: None,
-        };
+        let validator = list_of(BooleanType::default());
         assert_eq!(true, validator.validate(&json!([true])));
         assert_eq!(true, validator.validate(&json!([true, true])));
         assert_eq!(true, validator.validate(&json!([true, false])));
@@ -334,12 +1331,1116 @@ mod tests {
     #[test]
     fn list_type_should_limit_by_length() {
         let validator = ListType {
-            optional: false,
-            nullable: false,
-            element_type: DataType::Boolean(Box::new(BooleanType { optional: false, nullable: false })),
-            limit: Some(3),
+            max_items: Some(3),
+            ..list_of(BooleanType::default())
         };
         assert_eq!(true, validator.validate(&json!([true, true, true])));
         assert_eq!(false, validator.validate(&json!([true, true, true, true])));
     }
+
+    #[test]
+    fn dict_type_should_limit_by_max_properties() {
+        let mut map = HashMap::new();
+        map.insert("a".to_owned(), BooleanType { optional: true, ..Default::default() }.into());
+        map.insert("b".to_owned(), BooleanType { optional: true, ..Default::default() }.into());
+        let validator = DictType {
+            fields: map,
+            max_properties: Some(1),
+            ..Default::default()
+        };
+        assert_eq!(true, validator.validate(&json!({"a": true})));
+        assert_eq!(false, validator.validate(&json!({"a": true, "b": true})));
+    }
+
+    #[test]
+    fn dict_type_should_short_circuit_large_map_on_max_properties() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        // the selector runs once for every value the `others` type is asked about
+        let visited = Arc::new(AtomicUsize::new(0));
+        let counter = visited.clone();
+        let others = DynamicType::new(vec![BooleanType::default().into()], move |_| {
+            counter.fetch_add(1, Ordering::Relaxed);
+            Some(0)
+        });
+        let validator = DictType {
+            others: Some(DataType::Dynamic(others)),
+            max_properties: Some(10),
+            ..Default::default()
+        };
+        let object: serde_json::Map<String, Value> = (0..100_000).map(|i| (i.to_string(), Value::Bool(true))).collect();
+        let node = Value::Object(object);
+
+        let errors = validator.validate_detailed(&node).unwrap_err();
+        assert_eq!(vec![ErrorCode::TooManyProperties], errors.iter().map(|error| error.code).collect::<Vec<_>>());
+        assert_eq!(0, visited.load(Ordering::Relaxed));
+
+        // every value is a valid boolean, so only `max_properties` rejects the map
+        let unbounded = DictType { max_properties: None, ..validator };
+        assert_eq!(true, unbounded.validate(&node));
+        assert!(visited.load(Ordering::Relaxed) >= 100_000);
+    }
+
+    #[test]
+    fn types_only_should_skip_meta_constraints() {
+        let validator = ListType {
+            max_items: Some(1),
+            ..list_of(StringType { length: Some(3), regex: Some("[0-9]+".into()), ..Default::default() })
+        };
+        let options = ValidationOptions { types_only: true, ..Default::default() };
+        let node = json!(["abcd", "efgh"]);
+        assert_eq!(false, validator.validate(&node));
+        assert_eq!(true, validator.validate_with(&node, &options));
+        assert_eq!(false, validator.validate_with(&json!(["abcd", 1]), &options));
+    }
+
+    #[test]
+    fn string_type_should_check_date_time_format() {
+        let validator = StringType {
+            format: Some("date-time".to_owned()),
+            ..Default::default()
+        };
+        assert_eq!(true, validator.validate(&json!("2021-06-01T12:00:00Z")));
+        assert_eq!(false, validator.validate(&json!("2021-06-31T12:00:00Z")));
+        assert_eq!(false, validator.validate(&json!("yesterday")));
+    }
+
+    #[test]
+    fn date_time_should_be_relative_to_given_now() {
+        let now = std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_622_548_800); // 2021-06-01T12:00:00Z
+        let options = ValidationOptions { now: Some(now), ..Default::default() };
+
+        let future = StringType {
+            format: Some("date-time".to_owned()),
+            after: Some("now".to_owned()),
+            before: Some("now+7d".to_owned()),
+            ..Default::default()
+        };
+        assert_eq!(true, future.validate_with(&json!("2021-06-02T12:00:00Z"), &options));
+        assert_eq!(false, future.validate_with(&json!("2021-05-31T12:00:00Z"), &options));
+        assert_eq!(false, future.validate_with(&json!("2021-06-01T12:00:00Z"), &options));
+        assert_eq!(false, future.validate_with(&json!("2021-06-09T12:00:00Z"), &options));
+
+        let recent = StringType {
+            format: Some("date-time".to_owned()),
+            after: Some("now-30d".to_owned()),
+            before: Some("now".to_owned()),
+            ..Default::default()
+        };
+        assert_eq!(true, recent.validate_with(&json!("2021-05-20T08:00:00+02:00"), &options));
+        assert_eq!(false, recent.validate_with(&json!("2021-04-01T00:00:00Z"), &options));
+        assert_eq!(false, recent.validate_with(&json!("2021-06-02T00:00:00Z"), &options));
+    }
+
+    #[test]
+    fn dict_type_should_reject_empty_object_with_min_properties() {
+        let mut map = HashMap::new();
+        map.insert("name".to_owned(), DataType::String(Box::new(StringType { optional: true, ..Default::default() })));
+        map.insert("age".to_owned(), DataType::Number(Box::new(NumberType { optional: true, ..Default::default() })));
+        let validator = DictType {
+            fields: map,
+            min_properties: Some(1),
+            ..Default::default()
+        };
+        let errors = validator.validate_detailed(&json!({})).unwrap_err();
+        assert_eq!(ErrorCode::TooFewProperties, errors[0].code);
+        assert_eq!("at least one field is required", errors[0].expected);
+        assert_eq!(true, validator.validate(&json!({"name": "x"})));
+        assert_eq!(true, validator.validate(&json!({"name": "x", "age": 1})));
+    }
+
+    #[test]
+    fn list_type_should_be_permutation_of_exact_set() {
+        let validator = ListType {
+            exact_set: Some(vec![json!("a"), json!("b"), json!("c")]),
+            ..list_of(string())
+        };
+        assert_eq!(true, validator.validate(&json!(["a", "b", "c"])));
+        assert_eq!(true, validator.validate(&json!(["c", "a", "b"])));
+        assert_eq!(false, validator.validate(&json!(["a", "b"])));
+        assert_eq!(false, validator.validate(&json!(["a", "b", "c", "d"])));
+        assert_eq!(false, validator.validate(&json!(["a", "b", "b"])));
+
+        let duplicated = ListType {
+            element_type: DataType::Number(Box::default()),
+            exact_set: Some(vec![json!(1), json!(1), json!(2)]),
+            ..validator
+        };
+        assert_eq!(true, duplicated.validate(&json!([1, 2, 1.0])));
+        assert_eq!(false, duplicated.validate(&json!([1, 2, 2])));
+        assert_eq!(false, duplicated.validate(&json!([1, 1, 1])));
+    }
+
+    #[test]
+    fn string_type_should_check_decimal_format() {
+        let us = StringType {
+            format: Some("decimal".to_owned()),
+            grouping: Some(','),
+            ..Default::default()
+        };
+        assert_eq!(true, us.validate(&json!("1,234.56")));
+        assert_eq!(true, us.validate(&json!("1234")));
+        assert_eq!(false, us.validate(&json!("1.234,56")));
+        assert_eq!(false, us.validate(&json!("12,34")));
+
+        let european: StringType = serde_json::from_value(json!({
+            "format": "decimal",
+            "grouping": ".",
+            "decimal_separator": ","
+        }))
+        .unwrap();
+        assert_eq!(true, european.validate(&json!("1.234,56")));
+        assert_eq!(false, european.validate(&json!("1,234.56")));
+    }
+
+    #[test]
+    fn dict_type_should_reject_conflicting_fields() {
+        let mut map = HashMap::new();
+        map.insert("password".to_owned(), DataType::String(Box::new(StringType { optional: true, ..Default::default() })));
+        map.insert("token".to_owned(), DataType::String(Box::new(StringType { optional: true, ..Default::default() })));
+        map.insert("user".to_owned(), DataType::String(Box::default()));
+        let mut conflicts = HashMap::new();
+        conflicts.insert("password".to_owned(), vec!["token".to_owned()]);
+        let validator = DictType {
+            fields: map,
+            conflicts: Some(conflicts),
+            ..Default::default()
+        };
+        assert_eq!(true, validator.validate(&json!({"user": "a", "password": "x"})));
+        assert_eq!(true, validator.validate(&json!({"user": "a", "token": "y"})));
+        assert_eq!(false, validator.validate(&json!({"user": "a", "password": "x", "token": "y"})));
+    }
+
+    #[test]
+    fn unknown_format_should_fail_only_when_strict() {
+        let validator = StringType {
+            format: Some("phone".to_owned()),
+            ..Default::default()
+        };
+        let lenient = ValidationOptions { strict_formats: false, ..Default::default() };
+        assert_eq!(false, validator.validate(&json!("+8613800000000")));
+        assert_eq!(true, validator.validate_with(&json!("+8613800000000"), &lenient));
+    }
+
+    #[test]
+    fn string_type_should_check_hex_and_binary_format() {
+        let hex = StringType {
+            format: Some("hex".to_owned()),
+            bit_width: Some(8),
+            ..Default::default()
+        };
+        assert_eq!(true, hex.validate(&json!("ff")));
+        assert_eq!(true, hex.validate(&json!("0x0A")));
+        assert_eq!(false, hex.validate(&json!("0x1ff")));
+        assert_eq!(false, hex.validate(&json!("xyz")));
+
+        let binary = StringType {
+            format: Some("binary".to_owned()),
+            ..Default::default()
+        };
+        assert_eq!(true, binary.validate(&json!("0b1010")));
+        assert_eq!(true, binary.validate(&json!("1111111111111111111111111111111111111111111111111111111111111111111")));
+        assert_eq!(false, binary.validate(&json!("0b102")));
+    }
+
+    #[test]
+    fn dict_type_should_check_integer_key_sequence() {
+        let optional = BooleanType { optional: true, ..Default::default() };
+        let fields = (0..4).map(|i| (i.to_string(), optional.clone().into())).collect();
+        let validator = DictType {
+            fields,
+            integer_key_sequence: true,
+            ..Default::default()
+        };
+        assert_eq!(true, validator.validate(&json!({})));
+        assert_eq!(true, validator.validate(&json!({"0": true, "1": false, "2": true})));
+        assert_eq!(false, validator.validate(&json!({"0": true, "2": true})));
+        assert_eq!(false, validator.validate(&json!({"1": true})));
+    }
+
+    #[test]
+    fn date_time_should_check_offset() {
+        let any = StringType {
+            format: Some("date-time".to_owned()),
+            ..Default::default()
+        };
+        let with_offset = StringType {
+            format: Some("date-time".to_owned()),
+            require_offset: true,
+            ..Default::default()
+        };
+        let utc = StringType {
+            format: Some("date-time".to_owned()),
+            require_utc: true,
+            ..Default::default()
+        };
+        for validator in [&any, &with_offset, &utc] {
+            assert_eq!(true, validator.validate(&json!("2021-06-01T12:00:00Z")));
+        }
+        assert_eq!(true, any.validate(&json!("2021-06-01T12:00:00+02:00")));
+        assert_eq!(true, with_offset.validate(&json!("2021-06-01T12:00:00+02:00")));
+        assert_eq!(false, utc.validate(&json!("2021-06-01T12:00:00+02:00")));
+
+        assert_eq!(true, any.validate(&json!("2021-06-01T12:00:00")));
+        assert_eq!(false, with_offset.validate(&json!("2021-06-01T12:00:00")));
+        assert_eq!(false, utc.validate(&json!("2021-06-01T12:00:00")));
+    }
+
+    #[test]
+    fn number_type_should_check_safe_integer() {
+        let validator = NumberType {
+            safe_integer: true,
+            ..Default::default()
+        };
+        assert_eq!(true, validator.validate(&json!(9007199254740991u64)));
+        assert_eq!(true, validator.validate(&json!(-9007199254740991i64)));
+        assert_eq!(false, validator.validate(&json!(9007199254740992u64)));
+        assert_eq!(false, validator.validate(&json!(-9007199254740992i64)));
+        assert_eq!(false, validator.validate(&json!(u64::MAX)));
+        assert_eq!(false, validator.validate(&json!(1e20)));
+        assert_eq!(true, validator.validate(&json!(0.5)));
+        assert_eq!(true, NumberType::default().validate(&json!(9007199254740992u64)));
+    }
+
+    #[test]
+    fn dict_type_should_check_unique_values() {
+        let fields = ["a", "b", "c"]
+            .iter()
+            .map(|key| (key.to_string(), DataType::Number(Box::default())))
+            .collect();
+        let validator = DictType {
+            fields,
+            unique_values: true,
+            ..Default::default()
+        };
+        assert_eq!(true, validator.validate(&json!({"a": 1, "b": 2, "c": 3})));
+        assert_eq!(false, validator.validate(&json!({"a": 1, "b": 2, "c": 1})));
+        assert_eq!(false, validator.validate(&json!({"a": 1, "b": 1.0})));
+    }
+
+    #[test]
+    fn tagged_constraints_should_only_apply_with_enabled_feature() {
+        let validator = list_of(StringType {
+            features: vec!["beta".to_owned()],
+            length: Some(3),
+            ..Default::default()
+        });
+        let beta = ValidationOptions {
+            enabled_features: vec!["beta".to_owned()].into_iter().collect(),
+            ..Default::default()
+        };
+        assert_eq!(true, validator.validate(&json!(["abcd"])));
+        assert_eq!(false, validator.validate(&json!([1])));
+        assert_eq!(false, validator.validate_with(&json!(["abcd"]), &beta));
+        assert_eq!(true, validator.validate_with(&json!(["abc"]), &beta));
+    }
+
+    #[test]
+    fn string_type_should_fit_column() {
+        let varchar = StringType {
+            column: Some("varchar(255)".to_owned()),
+            ..Default::default()
+        };
+        assert_eq!(true, varchar.validate(&Value::String("a".repeat(255))));
+        assert_eq!(true, varchar.validate(&Value::String("中".repeat(255))));
+        assert_eq!(false, varchar.validate(&Value::String("a".repeat(256))));
+
+        let tinytext = StringType {
+            column: Some("tinytext".to_owned()),
+            ..Default::default()
+        };
+        assert_eq!(true, tinytext.validate(&Value::String("a".repeat(255))));
+        assert_eq!(false, tinytext.validate(&Value::String("中".repeat(100))));
+    }
+
+    #[test]
+    fn list_type_should_check_element_relation() {
+        let interval = DictType::default()
+            .field("start", NumberType::default())
+            .field("end", NumberType::default());
+        let validator = ListType {
+            element_relation: Some(ElementRelation {
+                previous: "end".to_owned(),
+                operator: Comparison::LessOrEqual,
+                current: "start".to_owned(),
+            }),
+            ..list_of(interval)
+        };
+        assert_eq!(true, validator.validate(&json!([])));
+        assert_eq!(true, validator.validate(&json!([{"start": 0, "end": 5}])));
+        assert_eq!(
+            true,
+            validator.validate(&json!([{"start": 0, "end": 5}, {"start": 5, "end": 6}, {"start": 8.5, "end": 9}]))
+        );
+        assert_eq!(false, validator.validate(&json!([{"start": 0, "end": 5}, {"start": 4, "end": 6}])));
+        assert_eq!(false, validator.validate(&json!([{"start": 0, "end": 5}, {"end": 6}])));
+    }
+
+    #[test]
+    fn dynamic_type_should_route_by_version() {
+        let v1 = DictType::default()
+            .field("version", NumberType::default())
+            .field("name", StringType::default());
+        let v2 = DictType::default()
+            .field("version", NumberType::default())
+            .field("first_name", StringType::default())
+            .field("last_name", StringType::default());
+        let validator = DynamicType::new(vec![v1.into(), v2.into()], |node| match node.get("version")?.as_u64()? {
+            1 => Some(0),
+            2 => Some(1),
+            _ => None,
+        });
+
+        assert_eq!(true, validator.validate(&json!({"version": 1, "name": "kilerd"})));
+        assert_eq!(true, validator.validate(&json!({"version": 2, "first_name": "k", "last_name": "d"})));
+        assert_eq!(false, validator.validate(&json!({"version": 2, "name": "kilerd"})));
+        assert_eq!(false, validator.validate(&json!({"version": 3})));
+        assert_eq!(false, validator.validate(&json!("v1")));
+    }
+
+    #[test]
+    fn list_type_should_be_strictly_sorted_by_key() {
+        let event = DictType::default()
+            .field("meta", DictType::default().field("seq", NumberType::default()));
+        let validator = ListType {
+            strictly_sorted_by: Some(vec!["meta".to_owned(), "seq".to_owned()]),
+            ..list_of(event)
+        };
+        let events = |seqs: Vec<i64>| Value::Array(seqs.into_iter().map(|seq| json!({"meta": {"seq": seq}})).collect());
+        assert_eq!(true, validator.validate(&events(vec![1, 2, 5])));
+        assert_eq!(false, validator.validate(&events(vec![1, 2, 2])));
+        assert_eq!(false, validator.validate(&events(vec![1, 3, 2])));
+        assert_eq!(false, validator.validate(&json!([{"meta": {"seq": 1}}, {"meta": {}}])));
+    }
+
+    #[test]
+    fn dict_type_should_match_exactly_one_shape() {
+        let fields = ["id", "email", "phone", "name"]
+            .iter()
+            .map(|key| (key.to_string(), StringType { optional: true, ..Default::default() }.into()))
+            .collect();
+        let shape = |keys: &[&str]| keys.iter().map(|key| key.to_string()).collect::<Vec<_>>();
+        let validator = DictType {
+            fields,
+            shapes: vec![shape(&["id"]), shape(&["email", "name"]), shape(&["phone", "name"])],
+            ..Default::default()
+        };
+        assert_eq!(true, validator.validate(&json!({"id": "1"})));
+        assert_eq!(true, validator.validate(&json!({"name": "k", "phone": "1"})));
+        assert_eq!(false, validator.validate(&json!({"name": "k"})));
+        assert_eq!(false, validator.validate(&json!({"id": "1", "email": "a@b"})));
+
+        let ambiguous = DictType {
+            shapes: vec![shape(&["id", "name"]), shape(&["name", "id"])],
+            ..validator
+        };
+        assert_eq!(false, ambiguous.validate(&json!({"id": "1", "name": "k"})));
+    }
+
+    #[test]
+    fn number_type_should_check_ratio_and_percentage() {
+        let ratio = NumberType {
+            format: Some(NumberFormat::Ratio),
+            ..Default::default()
+        };
+        assert_eq!(true, ratio.validate(&json!(0)));
+        assert_eq!(true, ratio.validate(&json!(0.25)));
+        assert_eq!(true, ratio.validate(&json!(1)));
+        assert_eq!(false, ratio.validate(&json!(1.01)));
+        assert_eq!(false, ratio.validate(&json!(-0.1)));
+
+        let percentage: NumberType = serde_json::from_value(json!({ "format": "percentage" })).unwrap();
+        assert_eq!(true, percentage.validate(&json!(0)));
+        assert_eq!(true, percentage.validate(&json!(42.5)));
+        assert_eq!(true, percentage.validate(&json!(100)));
+        assert_eq!(false, percentage.validate(&json!(100.5)));
+        assert_eq!(false, percentage.validate(&json!(-1)));
+    }
+
+    #[test]
+    fn max_nodes_should_reject_wide_documents() {
+        let validator = list_of(NumberType::default());
+        let options = ValidationOptions { max_nodes: Some(1000), ..Default::default() };
+        let fits = Value::Array((0..999).map(|index| json!(index)).collect());
+        let wide = Value::Array((0..100_000).map(|index| json!(index)).collect());
+
+        assert_eq!(true, validator.validate_with(&fits, &options));
+        assert_eq!(false, validator.validate_with(&wide, &options));
+        assert_eq!(true, validator.validate(&wide));
+    }
+
+    #[test]
+    fn string_type_should_match_strftime_pattern() {
+        let validator = StringType {
+            strftime: Some("%Y-%m-%d %H:%M:%S".to_owned()),
+            ..Default::default()
+        };
+        assert_eq!(true, validator.validate(&json!("2024-03-07 09:15:00")));
+        assert_eq!(false, validator.validate(&json!("2024-03-07T09:15:00Z")));
+    }
+
+    #[test]
+    fn string_type_should_measure_length_in_chosen_unit() {
+        let family = "\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}";
+        let bytes = StringType { length: Some(4), length_unit: LengthUnit::Bytes, ..Default::default() };
+        let chars = StringType { length: Some(4), ..Default::default() };
+        assert_eq!(false, bytes.validate(&json!(family)));
+        assert_eq!(false, chars.validate(&json!(family)));
+        assert_eq!(true, StringType { length: Some(5), ..chars }.validate(&json!(family)));
+    }
+
+    #[cfg(feature = "unicode-segmentation")]
+    #[test]
+    fn string_type_should_count_graphemes() {
+        let family = "\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}";
+        assert_eq!(18, LengthUnit::Bytes.measure(family));
+        assert_eq!(5, LengthUnit::Chars.measure(family));
+        assert_eq!(1, LengthUnit::Graphemes.measure(family));
+
+        let validator: StringType = serde_json::from_value(json!({ "length": 1, "length_unit": "graphemes" })).unwrap();
+        assert_eq!(true, validator.validate(&json!(family)));
+        assert_eq!(false, validator.validate(&json!("ab")));
+    }
+
+    #[test]
+    fn number_type_should_check_integer_domains() {
+        let port = NumberType {
+            format: Some(NumberFormat::Port),
+            ..Default::default()
+        };
+        assert_eq!(true, port.validate(&json!(1)));
+        assert_eq!(true, port.validate(&json!(65535)));
+        assert_eq!(false, port.validate(&json!(0)));
+        assert_eq!(false, port.validate(&json!(65536)));
+        assert_eq!(false, port.validate(&json!(80.5)));
+
+        let http_status: NumberType = serde_json::from_value(json!({ "format": "http_status" })).unwrap();
+        assert_eq!(true, http_status.validate(&json!(100)));
+        assert_eq!(true, http_status.validate(&json!(599)));
+        assert_eq!(false, http_status.validate(&json!(99)));
+        assert_eq!(false, http_status.validate(&json!(600)));
+        assert_eq!(false, http_status.validate(&json!(200.1)));
+    }
+
+    #[test]
+    fn nullable_type_should_accept_null() {
+        let nullable = StringType { nullable: true, ..Default::default() };
+        let required = StringType { nullable: false, ..Default::default() };
+        assert_eq!(true, nullable.validate(&Value::Null));
+        assert_eq!(true, nullable.validate(&json!("kilerd")));
+        assert_eq!(false, nullable.validate(&json!(1)));
+        assert_eq!(false, required.validate(&Value::Null));
+        assert_eq!(true, DataType::from(nullable).validate(&Value::Null));
+    }
+
+    #[test]
+    fn dict_type_should_require_non_optional_fields() {
+        let validator = DictType::default()
+            .field("name", StringType::default())
+            .field("nickname", StringType { optional: true, ..Default::default() });
+        assert_eq!(true, validator.validate(&json!({"name": "kilerd", "nickname": "k"})));
+        assert_eq!(true, validator.validate(&json!({"name": "kilerd"})));
+        assert_eq!(false, validator.validate(&json!({"nickname": "k"})));
+        assert_eq!(false, validator.validate(&json!({})));
+    }
+
+    #[test]
+    fn dict_type_should_validate_field_values() {
+        let validator = DictType::default()
+            .field("a", BooleanType::default())
+            .field("b", StringType { optional: true, length: Some(3), ..Default::default() });
+        assert_eq!(true, validator.validate(&json!({"a": true})));
+        assert_eq!(true, validator.validate(&json!({"a": false, "b": "abc"})));
+        assert_eq!(false, validator.validate(&json!({"a": 123})));
+        assert_eq!(false, validator.validate(&json!({"a": true, "b": "abcd"})));
+        assert_eq!(false, validator.validate(&json!({"a": true, "c": 1})));
+    }
+
+    #[test]
+    fn dict_type_should_match_count_to_array_length() {
+        let mut length_of = HashMap::new();
+        length_of.insert("count".to_owned(), "items".to_owned());
+        let validator = DictType {
+            length_of: Some(length_of),
+            ..DictType::default()
+                .field("count", NumberType::default())
+                .field("items", list_of(StringType::default()))
+        };
+        assert_eq!(true, validator.validate(&json!({"count": 2, "items": ["a", "b"]})));
+        assert_eq!(true, validator.validate(&json!({"count": 0, "items": []})));
+        assert_eq!(true, validator.validate(&json!({"count": 2.0, "items": ["a", "b"]})));
+        assert_eq!(false, validator.validate(&json!({"count": 3, "items": ["a", "b"]})));
+        assert_eq!(false, validator.validate(&json!({"count": 2.5, "items": ["a", "b"]})));
+    }
+
+    #[test]
+    fn dict_type_should_match_parallel_array_lengths() {
+        let validator = DictType {
+            parallel_arrays: vec![vec!["names".to_owned(), "ages".to_owned(), "emails".to_owned()]],
+            ..DictType::default()
+                .field("names", list_of(StringType::default()))
+                .field("ages", list_of(NumberType::default()))
+                .field("emails", ListType { optional: true, ..list_of(StringType::default()) })
+        };
+        assert_eq!(true, validator.validate(&json!({"names": ["a", "b"], "ages": [1, 2], "emails": ["a@b", "b@c"]})));
+        assert_eq!(true, validator.validate(&json!({"names": [], "ages": [], "emails": []})));
+        assert_eq!(true, validator.validate(&json!({"names": ["a", "b"], "ages": [1, 2]})));
+
+        let errors = validator.validate_detailed(&json!({"names": ["a", "b"], "ages": [1], "emails": ["a@b"]})).unwrap_err();
+        assert_eq!(2, errors.len());
+        assert!(errors.iter().all(|error| error.code == ErrorCode::LengthMismatch));
+        assert_eq!(vec!["/ages", "/emails"], errors.iter().map(|error| error.path.as_str()).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn string_type_should_reject_surrounding_whitespace() {
+        let validator = StringType {
+            no_surrounding_whitespace: true,
+            ..Default::default()
+        };
+        assert_eq!(true, validator.validate(&json!("kilerd")));
+        assert_eq!(true, validator.validate(&json!("hello world")));
+        assert_eq!(true, validator.validate(&json!("")));
+        assert_eq!(false, validator.validate(&json!(" kilerd")));
+        assert_eq!(false, validator.validate(&json!("kilerd\n")));
+        assert_eq!(false, validator.validate(&json!("\u{3000}kilerd")));
+    }
+
+    #[test]
+    fn dict_type_should_match_any_fields_patterns() {
+        let validator: DictType = serde_json::from_value(json!({
+            "fields": { "default": { "type": "String" } },
+            "any_fields": { "[a-z]{2}_[A-Z]{2}": { "type": "String" } }
+        }))
+        .unwrap();
+        assert_eq!(true, validator.validate(&json!({"default": "hello", "zh_CN": "你好", "en_US": "hello"})));
+        assert_eq!(false, validator.validate(&json!({"default": "hello", "zh_CN": 1})));
+        assert_eq!(false, validator.validate(&json!({"default": "hello", "chinese": "你好"})));
+        let alternation: DictType = serde_json::from_value(json!({ "fields": {}, "any_fields": { "x|y": { "type": "Number" } } })).unwrap();
+        assert_eq!(true, alternation.validate(&json!({"x": 1, "y": 2})));
+        assert_eq!(false, alternation.validate(&json!({"xylophone": 1})));
+    }
+
+    #[test]
+    fn dict_type_should_validate_other_keys_against_others() {
+        let validator = DictType {
+            others: Some(NumberType::default().into()),
+            ..DictType::default()
+        };
+        assert_eq!(true, validator.validate(&json!({"x": 1, "y": 2})));
+        assert_eq!(false, validator.validate(&json!({"x": "str"})));
+        assert_eq!(false, DictType::default().validate(&json!({"x": 1})));
+    }
+
+    #[test]
+    fn number_type_should_check_bounds() {
+        let inclusive = NumberType {
+            minimum: Some(0.0),
+            maximum: Some(10.0),
+            ..Default::default()
+        };
+        assert_eq!(true, inclusive.validate(&json!(0)));
+        assert_eq!(true, inclusive.validate(&json!(10.0)));
+        assert_eq!(false, inclusive.validate(&json!(-0.5)));
+        assert_eq!(false, inclusive.validate(&json!(11)));
+
+        let exclusive = NumberType {
+            exclusive_minimum: true,
+            exclusive_maximum: true,
+            ..inclusive
+        };
+        assert_eq!(false, exclusive.validate(&json!(0)));
+        assert_eq!(false, exclusive.validate(&json!(10.0)));
+        assert_eq!(true, exclusive.validate(&json!(0.001)));
+        assert_eq!(true, exclusive.validate(&json!(9)));
+    }
+
+    #[test]
+    fn dict_type_should_make_every_field_optional() {
+        let address = DictType::default().field("city", StringType::default());
+        let patch = DictType {
+            all_optional: true,
+            ..DictType::default()
+                .field("name", StringType::default())
+                .field("address", address)
+        };
+        assert_eq!(true, patch.validate(&json!({})));
+        assert_eq!(true, patch.validate(&json!({"name": "kilerd"})));
+        assert_eq!(false, patch.validate(&json!({"name": 1})));
+        assert_eq!(false, patch.validate(&json!({"address": {}})));
+    }
+
+    #[test]
+    fn number_type_should_check_integer() {
+        let validator = NumberType {
+            integer: true,
+            ..Default::default()
+        };
+        assert_eq!(true, validator.validate(&json!(2)));
+        assert_eq!(true, validator.validate(&json!(-2)));
+        assert_eq!(true, validator.validate(&serde_json::from_str::<Value>("2.0").unwrap()));
+        assert_eq!(false, validator.validate(&json!(2.5)));
+        assert_eq!(true, NumberType::default().validate(&json!(2.5)));
+    }
+
+    #[test]
+    fn string_type_length_should_count_characters() {
+        let cjk = Value::String("一二三四五六七八九十".to_owned());
+        assert_eq!(30, cjk.as_str().unwrap().len());
+        assert_eq!(true, StringType { length: Some(10), ..Default::default() }.validate(&cjk));
+        assert_eq!(false, StringType { length: Some(9), ..Default::default() }.validate(&cjk));
+    }
+
+    #[test]
+    fn string_type_should_check_min_length() {
+        let non_empty = StringType { min_length: Some(1), ..Default::default() };
+        assert_eq!(false, non_empty.validate(&json!("")));
+        assert_eq!(true, non_empty.validate(&json!("a")));
+
+        let three = StringType { min_length: Some(3), ..Default::default() };
+        assert_eq!(false, three.validate(&json!("ab")));
+        assert_eq!(true, three.validate(&json!("abc")));
+        assert_eq!(true, three.validate(&json!("中文字")));
+        assert_eq!(false, three.validate(&json!("中文")));
+    }
+
+    #[test]
+    fn list_type_should_be_unique_ignoring_keys() {
+        let event = DictType::default()
+            .field("id", NumberType::default())
+            .field("at", StringType::default());
+        let validator = ListType {
+            unique_ignoring: Some(vec!["at".to_owned()]),
+            ..list_of(event)
+        };
+        assert_eq!(true, validator.validate(&json!([{"id": 1, "at": "10:00"}, {"id": 2, "at": "10:00"}])));
+        assert_eq!(false, validator.validate(&json!([{"id": 1, "at": "10:00"}, {"id": 1, "at": "10:05"}])));
+        assert_eq!(false, validator.validate(&json!([{"id": 1, "at": "10:00"}, {"id": 2, "at": "10:00"}, {"id": 1.0, "at": "11:00"}])));
+    }
+
+    #[test]
+    fn number_type_should_check_f32_round_trip() {
+        let validator = NumberType {
+            fits_f32: true,
+            ..Default::default()
+        };
+        assert_eq!(true, validator.validate(&json!(0.5)));
+        assert_eq!(true, validator.validate(&json!(16777216)));
+        assert_eq!(true, validator.validate(&json!(1.5e9)));
+        assert_eq!(false, validator.validate(&json!(0.1)));
+        assert_eq!(false, validator.validate(&json!(16777217)));
+        assert_eq!(false, validator.validate(&json!(1e300)));
+    }
+
+    #[test]
+    fn validate_detailed_should_report_path_of_failing_element() {
+        let validator = DictType::default().field("items", list_of(NumberType::default()));
+        let errors = validator.validate_detailed(&json!({"items": [1, 2, "three", 4]})).unwrap_err();
+        assert_eq!(
+            vec![ValidationError {
+                code: ErrorCode::WrongType,
+                path: "/items/2".to_owned(),
+                expected: "a number".to_owned(),
+                actual: json!("three"),
+            }],
+            errors
+        );
+        assert_eq!(Ok(()), validator.validate_detailed(&json!({"items": [1, 2]})));
+    }
+
+    #[test]
+    fn validate_detailed_should_collect_every_error() {
+        let validator = DictType::default()
+            .field("name", StringType { length: Some(3), ..Default::default() })
+            .field("age", NumberType { minimum: Some(0.0), ..Default::default() })
+            .field("admin", BooleanType::default());
+        let mut errors = validator
+            .validate_detailed(&json!({"name": "kilerd", "age": -1, "extra": true}))
+            .unwrap_err();
+        errors.sort_by(|left, right| left.path.cmp(&right.path));
+        let paths: Vec<&str> = errors.iter().map(|error| error.path.as_str()).collect();
+        assert_eq!(vec!["/admin", "/age", "/extra", "/name"], paths);
+        let codes: Vec<ErrorCode> = errors.iter().map(|error| error.code).collect();
+        assert_eq!(
+            vec![ErrorCode::MissingField, ErrorCode::BelowMinimum, ErrorCode::UnknownField, ErrorCode::TooLong],
+            codes
+        );
+        assert_eq!("/name: expected at most 3 characters, found \"kilerd\"", errors[3].to_string());
+    }
+
+    #[test]
+    fn max_errors_should_stop_collecting() {
+        let validator = list_of(NumberType::default());
+        let node = json!(["a", "b", "c", "d"]);
+        assert_eq!(4, validator.validate_detailed(&node).unwrap_err().len());
+
+        let options = ValidationOptions { max_errors: Some(2), ..Default::default() };
+        let errors = validator.validate_detailed_with(&node, &options).unwrap_err();
+        assert_eq!(vec!["/0", "/1"], errors.iter().map(|error| error.path.as_str()).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn container_errors_should_keep_a_summary_of_the_node() {
+        let validator = DictType {
+            max_properties: Some(2),
+            others: Some(NumberType::default().into()),
+            ..Default::default()
+        };
+        let object: serde_json::Map<String, Value> = (0..20).map(|i| (format!("k{:02}", i), json!([[i], [[i]]]))).collect();
+        let errors = validator.validate_detailed(&Value::Object(object)).unwrap_err();
+        assert_eq!(ErrorCode::TooManyProperties, errors[0].code);
+        let actual = errors[0].actual.as_object().unwrap();
+        assert_eq!(9, actual.len());
+        assert_eq!(json!("12 more"), actual["..."]);
+        assert_eq!(json!(["[1 items]", "[1 items]"]), actual["k00"]);
+    }
+
+    #[test]
+    fn dict_type_should_restrict_property_names() {
+        let validator = DictType {
+            property_names: Some(literal(&["mon", "tue", "wed"])),
+            others: Some(NumberType::default().into()),
+            ..Default::default()
+        };
+        assert_eq!(true, validator.validate(&json!({"mon": 8, "wed": 6})));
+        assert_eq!(false, validator.validate(&json!({"mon": 8, "sun": 0})));
+        assert_eq!(false, validator.validate(&json!({"tue": "eight"})));
+    }
+
+    #[test]
+    fn validate_detailed_should_report_full_path_through_dicts_and_lists() {
+        let user = DictType::default()
+            .field("name", StringType::default())
+            .field("email", StringType::default());
+        let validator = DictType::default().field("users", list_of(user));
+        let errors = validator
+            .validate_detailed(&json!({"users": [{"name": "a", "email": 1}, {"name": "b", "email": "b@b"}]}))
+            .unwrap_err();
+        assert_eq!(1, errors.len());
+        assert_eq!("/users/0/email", errors[0].path);
+        assert_eq!(json!(1), errors[0].actual);
+    }
+
+    #[test]
+    fn number_type_should_check_grid() {
+        let half_steps = NumberType {
+            grid: Some(Grid { step: 1.0, offset: 0.5 }),
+            ..Default::default()
+        };
+        assert_eq!(true, half_steps.validate(&json!(0.5)));
+        assert_eq!(true, half_steps.validate(&json!(2.5)));
+        assert_eq!(true, half_steps.validate(&json!(-1.5)));
+        assert_eq!(false, half_steps.validate(&json!(2)));
+        assert_eq!(false, half_steps.validate(&json!(2.25)));
+
+        let tenths: NumberType = serde_json::from_value(json!({ "grid": { "step": 0.1 } })).unwrap();
+        assert_eq!(true, tenths.validate(&json!(0.3)));
+        assert_eq!(false, tenths.validate(&json!(0.35)));
+    }
+
+    #[test]
+    fn regex_should_be_compiled_once_for_a_large_array() {
+        let validator = list_of(string().matches("[0-9]+"));
+        let mut items: Vec<Value> = (0..10_000).map(|i| json!(i.to_string())).collect();
+        assert_eq!(true, validator.validate(&Value::Array(items.clone())));
+
+        let regex = match &validator.element_type {
+            DataType::String(inner) => inner.regex.as_ref().unwrap().regex().unwrap() as *const _,
+            _ => unreachable!(),
+        };
+        items[9_999] = json!("x");
+        let errors = validator.validate_detailed(&Value::Array(items)).unwrap_err();
+        assert_eq!(1, errors.len());
+        assert_eq!("/9999", errors[0].path);
+        match &validator.element_type {
+            DataType::String(inner) => assert!(std::ptr::eq(regex, inner.regex.as_ref().unwrap().regex().unwrap())),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn dict_type_should_check_graph_integrity() {
+        let validator: DictType = serde_json::from_value(json!({
+            "fields": {},
+            "others": { "type": "Dict", "fields": { "depends_on": { "type": "List", "element_type": { "type": "String" } } } },
+            "graph_integrity": { "edges": "depends_on", "acyclic": true }
+        }))
+        .unwrap();
+        let dag = json!({
+            "app": { "depends_on": ["db", "cache"] },
+            "cache": { "depends_on": ["db"] },
+            "db": { "depends_on": [] }
+        });
+        assert_eq!(true, validator.validate(&dag));
+
+        let dangling = json!({ "app": { "depends_on": ["db", "queue"] }, "db": { "depends_on": [] } });
+        let errors = validator.validate_detailed(&dangling).unwrap_err();
+        assert_eq!(1, errors.len());
+        assert_eq!(ErrorCode::DanglingReference, errors[0].code);
+        assert_eq!("/app/depends_on/1", errors[0].path);
+
+        let cycle = json!({
+            "app": { "depends_on": ["db"] },
+            "cache": { "depends_on": ["app"] },
+            "db": { "depends_on": ["cache"] }
+        });
+        let errors = validator.validate_detailed(&cycle).unwrap_err();
+        assert_eq!(1, errors.len());
+        assert_eq!(ErrorCode::Cycle, errors[0].code);
+        assert_eq!("/app", errors[0].path);
+    }
+
+    #[test]
+    fn graph_integrity_should_follow_long_chains() {
+        let validator: DictType = serde_json::from_value(json!({
+            "fields": {},
+            "others": { "type": "List", "element_type": { "type": "String" } },
+            "graph_integrity": { "acyclic": true }
+        }))
+        .unwrap();
+        let length = 200_000;
+        let mut chain: serde_json::Map<String, Value> =
+            (0..length).map(|index| (format!("n{}", index), json!([format!("n{}", index + 1)]))).collect();
+        chain.insert(format!("n{}", length), json!([]));
+        let mut chain = Value::Object(chain);
+        assert_eq!(true, validator.validate(&chain));
+
+        chain[format!("n{}", length)] = json!(["n0"]);
+        let errors = validator.validate_detailed(&chain).unwrap_err();
+        assert_eq!(vec![ErrorCode::Cycle], errors.iter().map(|error| error.code).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn graph_integrity_should_read_bare_edge_lists() {
+        let validator: DictType = serde_json::from_value(json!({
+            "fields": {},
+            "others": { "type": "List", "element_type": { "type": "String" } },
+            "graph_integrity": {}
+        }))
+        .unwrap();
+        assert_eq!(true, validator.validate(&json!({ "a": ["b"], "b": ["a"] })));
+        assert_eq!(false, validator.validate(&json!({ "a": ["c"] })));
+    }
+
+    #[test]
+    fn number_type_should_check_candidates() {
+        let validator = NumberType {
+            candidate: Some(vec![1.0, 2.0, 4.0]),
+            ..Default::default()
+        };
+        assert_eq!(true, validator.validate(&json!(2)));
+        assert_eq!(true, validator.validate(&json!(4.0)));
+        assert_eq!(false, validator.validate(&json!(3)));
+
+        let errors = validator.validate_detailed(&json!(3)).unwrap_err();
+        assert_eq!(ErrorCode::NotACandidate, errors[0].code);
+        assert_eq!("one of 1, 2, 4", errors[0].expected);
+    }
+
+    #[test]
+    fn tuple_type_should_check_every_position() {
+        let validator: DataType = serde_json::from_value(json!({
+            "type": "Tuple",
+            "elements": [{ "type": "String" }, { "type": "Number" }, { "type": "Boolean" }]
+        }))
+        .unwrap();
+        assert_eq!(true, validator.validate(&json!(["kilerd", 24, true])));
+
+        let errors = validator.validate_detailed(&json!(["kilerd", "24", true])).unwrap_err();
+        assert_eq!(1, errors.len());
+        assert_eq!((ErrorCode::WrongType, "/1"), (errors[0].code, errors[0].path.as_str()));
+
+        let errors = validator.validate_detailed(&json!(["kilerd", 24])).unwrap_err();
+        assert_eq!((ErrorCode::WrongLength, "exactly 3 elements"), (errors[0].code, errors[0].expected.as_str()));
+        assert_eq!(false, validator.validate(&json!(["kilerd", 24, true, false])));
+    }
+
+    #[test]
+    fn tuple_type_should_check_trailing_elements_against_rest() {
+        let validator: DataType = serde_json::from_value(json!({
+            "type": "Tuple",
+            "elements": [{ "type": "String" }],
+            "rest": { "type": "Number" }
+        }))
+        .unwrap();
+        assert_eq!(true, validator.validate(&json!(["sum"])));
+        assert_eq!(true, validator.validate(&json!(["sum", 1, 2, 3])));
+        assert_eq!(false, validator.validate(&json!(["sum", 1, "2"])));
+        assert_eq!(false, validator.validate(&json!([])));
+    }
+
+    #[test]
+    fn list_type_should_check_probability_distribution() {
+        let validator: ListType = serde_json::from_value(json!({
+            "element_type": { "type": "Number" },
+            "probability_distribution": { "tolerance": 1e-9 }
+        }))
+        .unwrap();
+        assert_eq!(true, validator.validate(&json!([0.1, 0.2, 0.7])));
+        assert_eq!(true, validator.validate(&json!([1])));
+
+        let errors = validator.validate_detailed(&json!([0.5, 0.6])).unwrap_err();
+        assert_eq!(1, errors.len());
+        assert_eq!((ErrorCode::NotADistribution, ""), (errors[0].code, errors[0].path.as_str()));
+
+        let errors = validator.validate_detailed(&json!([1.2, -0.2])).unwrap_err();
+        assert_eq!(1, errors.len());
+        assert_eq!((ErrorCode::BelowMinimum, "/1"), (errors[0].code, errors[0].path.as_str()));
+    }
+
+    #[test]
+    fn one_of_type_should_accept_any_variant() {
+        let validator: DataType = serde_json::from_value(json!({
+            "type": "OneOf",
+            "variants": [{ "type": "String", "regex": "[0-9]+" }, { "type": "Number", "minimum": 1 }]
+        }))
+        .unwrap();
+        assert_eq!(true, validator.validate(&json!("8080")));
+        assert_eq!(true, validator.validate(&json!(8080)));
+
+        let errors = validator.validate_detailed(&json!("http")).unwrap_err();
+        assert_eq!(
+            vec![ErrorCode::NoMatchingVariant, ErrorCode::PatternMismatch, ErrorCode::WrongType],
+            errors.iter().map(|error| error.code).collect::<Vec<_>>()
+        );
+        let errors = validator.validate_detailed(&json!(true)).unwrap_err();
+        assert_eq!(
+            vec![ErrorCode::NoMatchingVariant, ErrorCode::WrongType, ErrorCode::WrongType],
+            errors.iter().map(|error| error.code).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn one_of_type_should_accept_null_for_a_nullable_variant() {
+        let validator: DataType = serde_json::from_value(json!({
+            "type": "OneOf",
+            "variants": [{ "type": "String", "nullable": true }, { "type": "Number" }]
+        }))
+        .unwrap();
+        assert_eq!(true, validator.validate_type(&Value::Null));
+        assert_eq!(true, validator.validate(&Value::Null));
+        assert_eq!(false, validator.validate(&json!(true)));
+    }
+
+    #[test]
+    fn validate_outcome_should_record_the_matched_branches() {
+        let validator: DataType = serde_json::from_value(json!({
+            "type": "Dict",
+            "fields": { "id": { "type": "OneOf", "variants": [{ "type": "String" }, { "type": "Number" }] } },
+            "any_fields": { "[a-z]{2}_[A-Z]{2}": { "type": "String" } }
+        }))
+        .unwrap();
+        let options = ValidationOptions::default();
+
+        let outcome = validator.validate_outcome(&json!({"id": 1, "zh_CN": "你好"}), &options);
+        assert!(outcome.errors.is_empty());
+        assert_eq!(
+            vec![("/id".to_owned(), Branch::Variant(1)), ("/zh_CN".to_owned(), Branch::Pattern("[a-z]{2}_[A-Z]{2}".to_owned()))],
+            outcome.branches
+        );
+        assert_eq!(vec![("/id".to_owned(), Branch::Variant(0))], validator.validate_outcome(&json!({"id": "1"}), &options).branches);
+
+        let invalid = validator.validate_outcome(&json!({"id": true}), &options);
+        assert_eq!(ErrorCode::NoMatchingVariant, invalid.errors[0].code);
+        assert!(invalid.branches.is_empty());
+    }
+
+    #[test]
+    fn validation_outcome_should_serialize_to_json() {
+        let validator = DictType::default().field("name", StringType { length: Some(3), ..Default::default() });
+        let outcome = validator.validate_outcome(&json!({"name": "kilerd"}), &ValidationOptions::default());
+        let serialized = serde_json::to_value(&outcome).unwrap();
+
+        assert_eq!(
+            json!({
+                "valid": false,
+                "errors": [{ "code": "too_long", "path": "/name", "expected": "at most 3 characters", "actual": "kilerd" }],
+                "warnings": [],
+                "stats": { "nodes": 2 },
+                "branches": []
+            }),
+            serialized
+        );
+        assert_eq!(outcome, serde_json::from_value(serialized).unwrap());
+
+        let stopped = validator.validate_outcome(&json!({"name": "kilerd", "admin": true}), &ValidationOptions { max_errors: Some(1), ..Default::default() });
+        assert_eq!(vec!["stopped after 1 errors, the document may have more".to_owned()], stopped.warnings);
+
+        let one_of: DataType = serde_json::from_value(json!({ "type": "OneOf", "variants": [{ "type": "String" }, { "type": "Number" }] })).unwrap();
+        let branches = serde_json::to_value(one_of.validate_outcome(&json!(1), &ValidationOptions::default()).branches).unwrap();
+        assert_eq!(json!([["", { "variant": 1 }]]), branches);
+        assert!(serde_json::from_value::<ErrorCode>(json!("nope")).is_err());
+    }
+
+    #[test]
+    fn string_type_should_check_uuid_version() {
+        let validator = StringType {
+            format: Some("uuid".to_owned()),
+            uuid_version: Some(4),
+            ..Default::default()
+        };
+        assert_eq!(true, validator.validate(&json!("123e4567-e89b-42d3-a456-426614174000")));
+        assert_eq!(false, validator.validate(&json!("c232ab00-9414-11ec-b3c8-9f6bdeced846")));
+        assert_eq!(false, validator.validate(&json!("not-a-uuid")));
+
+        let any_version = StringType { format: Some("uuid".to_owned()), ..Default::default() };
+        assert_eq!(true, any_version.validate(&json!("c232ab00-9414-11ec-b3c8-9f6bdeced846")));
+    }
+
+    #[test]
+    fn null_type_should_only_accept_null() {
+        let validator = NullType::default();
+        assert_eq!(true, validator.validate(&Value::Null));
+        for node in [json!(false), json!(0), json!(""), json!([]), json!({})] {
+            assert_eq!(false, validator.validate(&node), "{} passed", node);
+        }
+    }
+
+    #[test]
+    fn max_serialized_bytes_should_limit_the_whole_document() {
+        let validator = list_of(string());
+        // `["aaaa","bbbb"]` is 15 bytes
+        let node = json!(["aaaa", "bbbb"]);
+        let fits = ValidationOptions { max_serialized_bytes: Some(15), ..Default::default() };
+        assert_eq!(true, validator.validate_with(&node, &fits));
+
+        let over = ValidationOptions { max_serialized_bytes: Some(14), ..Default::default() };
+        let errors = validator.validate_detailed_with(&node, &over).unwrap_err();
+        assert_eq!((ErrorCode::TooLarge, ""), (errors[0].code, errors[0].path.as_str()));
+
+        // reported alone, the elements are never looked at
+        let wrong_elements = json!([1, 2, 3, 4, 5, 6, 7]);
+        let errors = validator.validate_detailed_with(&wrong_elements, &over).unwrap_err();
+        assert_eq!(vec![ErrorCode::TooLarge], errors.iter().map(|error| error.code).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn string_type_should_check_pointer_formats() {
+        let pointer = string().format("json-pointer");
+        assert_eq!(true, pointer.validate(&json!("/users/0/email")));
+        assert_eq!(false, pointer.validate(&json!("users/0/email")));
+
+        let path = string().format("jsonpath");
+        assert_eq!(true, path.validate(&json!("$.users[*].email")));
+        assert_eq!(false, path.validate(&json!("$.users[*")));
+    }
+
+    #[test]
+    fn optional_and_nullable_should_combine_as_documented() {
+        // (optional, nullable, absent accepted, null accepted)
+        let table = [
+            (false, false, false, false),
+            (false, true, false, true),
+            (true, false, true, false),
+            (true, true, true, true),
+        ];
+        for (optional, nullable, absent, null) in table {
+            let validator = DictType::default().field("nickname", StringType { optional, nullable, ..Default::default() });
+            let case = format!("optional: {}, nullable: {}", optional, nullable);
+            assert_eq!(absent, validator.validate(&json!({})), "absent with {}", case);
+            assert_eq!(null, validator.validate(&json!({ "nickname": null })), "null with {}", case);
+            assert_eq!(true, validator.validate(&json!({ "nickname": "kilerd" })), "present with {}", case);
+        }
+    }
 }
@@ -0,0 +1,88 @@
+//! Comparing JSON values by meaning rather than representation.
+
+use std::cmp::Ordering;
+
+use serde_json::{Number, Value};
+
+fn number_eq(left: &Number, right: &Number) -> bool {
+    match (left.as_i64(), right.as_i64(), left.as_u64(), right.as_u64()) {
+        (Some(left), Some(right), _, _) => left == right,
+        (_, _, Some(left), Some(right)) => left == right,
+        _ => left.as_f64() == right.as_f64(),
+    }
+}
+
+/// Equality where `1` and `1.0` are the same number, recursively through arrays and objects.
+pub fn canonical_eq(left: &Value, right: &Value) -> bool {
+    match (left, right) {
+        (Value::Number(left), Value::Number(right)) => number_eq(left, right),
+        (Value::Array(left), Value::Array(right)) => {
+            left.len() == right.len() && left.iter().zip(right).all(|(left, right)| canonical_eq(left, right))
+        }
+        (Value::Object(left), Value::Object(right)) => {
+            left.len() == right.len()
+                && left
+                    .iter()
+                    .all(|(key, value)| right.get(key).is_some_and(|other| canonical_eq(value, other)))
+        }
+        _ => left == right,
+    }
+}
+
+fn number_cmp(left: &Number, right: &Number) -> Option<Ordering> {
+    match (left.as_i64(), right.as_i64(), left.as_u64(), right.as_u64()) {
+        (Some(left), Some(right), _, _) => Some(left.cmp(&right)),
+        (_, _, Some(left), Some(right)) => Some(left.cmp(&right)),
+        _ => left.as_f64()?.partial_cmp(&right.as_f64()?),
+    }
+}
+
+/// Order numbers by value and strings lexicographically, anything else is not comparable.
+pub fn canonical_cmp(left: &Value, right: &Value) -> Option<Ordering> {
+    match (left, right) {
+        (Value::Number(left), Value::Number(right)) => number_cmp(left, right),
+        (Value::String(left), Value::String(right)) => Some(left.cmp(right)),
+        _ => None,
+    }
+}
+
+/// Follow a path of object keys down into a value.
+pub fn project<'a>(node: &'a Value, path: &[String]) -> Option<&'a Value> {
+    path.iter().try_fold(node, |node, key| node.get(key))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cmp::Ordering;
+
+    use serde_json::json;
+
+    use crate::value::{canonical_cmp, canonical_eq};
+
+    #[test]
+    fn numbers_should_be_compared_by_value() {
+        assert!(canonical_eq(&json!(1), &json!(1.0)));
+        assert!(canonical_eq(&json!(-1), &json!(-1.0)));
+        assert!(canonical_eq(&json!(u64::MAX), &json!(u64::MAX)));
+        assert!(!canonical_eq(&json!(1), &json!(1.5)));
+        assert!(!canonical_eq(&json!(1), &json!("1")));
+    }
+
+    #[test]
+    fn containers_should_be_compared_recursively() {
+        assert!(canonical_eq(&json!([1, {"a": 2}]), &json!([1.0, {"a": 2.0}])));
+        assert!(!canonical_eq(&json!([1, 2]), &json!([2, 1])));
+        assert!(!canonical_eq(&json!({"a": 1}), &json!({"a": 1, "b": 2})));
+        assert!(!canonical_eq(&json!({"a": 1}), &json!({"b": 1})));
+    }
+
+    #[test]
+    fn numbers_and_strings_should_be_ordered() {
+        assert_eq!(Some(Ordering::Equal), canonical_cmp(&json!(1), &json!(1.0)));
+        assert_eq!(Some(Ordering::Less), canonical_cmp(&json!(-1), &json!(u64::MAX)));
+        assert_eq!(Some(Ordering::Greater), canonical_cmp(&json!(2.5), &json!(2)));
+        assert_eq!(Some(Ordering::Less), canonical_cmp(&json!("a"), &json!("b")));
+        assert_eq!(None, canonical_cmp(&json!("1"), &json!(1)));
+        assert_eq!(None, canonical_cmp(&json!(true), &json!(false)));
+    }
+}
@@ -0,0 +1,247 @@
+#![cfg(feature = "binary")]
+
+use std::path::PathBuf;
+use std::process::Command;
+
+fn fixture(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(name)
+}
+
+fn say() -> Command {
+    Command::new(env!("CARGO_BIN_EXE_say"))
+}
+
+#[test]
+fn check_examples_should_report_broken_golden_files() {
+    let output = say()
+        .arg("check-examples")
+        .arg(fixture("user.schema.json"))
+        .arg("--examples-dir")
+        .arg(fixture("examples"))
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    assert_eq!(Some(1), output.status.code());
+    assert!(stdout.lines().any(|line| line.starts_with("ok") && line.ends_with("conforming.json")));
+    assert!(stdout.lines().any(|line| line.starts_with("broken") && line.ends_with("broken.json")));
+}
+
+#[test]
+fn doc_should_print_a_markdown_table() {
+    let output = say().arg("doc").arg(fixture("user.schema.json")).output().unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    assert!(output.status.success());
+    assert!(stdout.starts_with("| Field | Type | Required | Constraints | Description |"));
+    assert!(stdout.lines().any(|line| line.starts_with("| `admin` | Boolean | yes |")));
+}
+
+#[test]
+fn migrate_should_rewrite_deprecated_fields() {
+    let schema = std::env::temp_dir().join(format!("say-migrate-{}.schema.json", std::process::id()));
+    std::fs::write(
+        &schema,
+        r#"{ "root": { "type": "List", "limit": 3, "element_type": { "type": "String" } }, "validators": [] }"#,
+    )
+    .unwrap();
+
+    let output = say().arg("migrate").arg(&schema).output().unwrap();
+    let migrated: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&schema).unwrap()).unwrap();
+    std::fs::remove_file(&schema).unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(serde_json::json!(3), migrated["root"]["max_items"]);
+    assert_eq!(None, migrated["root"].get("limit"));
+}
+
+#[test]
+fn say_should_validate_file_against_schema() {
+    let valid = say()
+        .arg("--schema")
+        .arg(fixture("user.schema.json"))
+        .arg(fixture("examples/conforming.json"))
+        .output()
+        .unwrap();
+    assert_eq!(Some(0), valid.status.code());
+    assert!(String::from_utf8(valid.stdout).unwrap().starts_with("valid"));
+
+    let invalid = say()
+        .arg("--schema")
+        .arg(fixture("user.schema.json"))
+        .arg(fixture("examples/broken.json"))
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(invalid.stdout).unwrap();
+    assert_eq!(Some(1), invalid.status.code());
+    assert!(stdout.starts_with("invalid"));
+    assert!(stdout.contains("/email"));
+}
+
+#[test]
+fn bitflag_exit_should_encode_the_kinds_of_failure() {
+    // `email` is undeclared (a constraint error) and `admin` is missing
+    let output = say()
+        .arg("--bitflag-exit")
+        .arg("--schema")
+        .arg(fixture("user.schema.json"))
+        .arg(fixture("examples/broken.json"))
+        .output()
+        .unwrap();
+    assert_eq!(Some(2 | 4), output.status.code());
+}
+
+#[test]
+fn say_should_report_unreadable_input_without_panicking() {
+    let missing = say()
+        .arg("--schema")
+        .arg(fixture("user.schema.json"))
+        .arg(fixture("examples/missing.json"))
+        .output()
+        .unwrap();
+    assert_eq!(Some(1), missing.status.code());
+    assert!(String::from_utf8(missing.stderr).unwrap().starts_with("error: cannot read"));
+
+    let schema = std::env::temp_dir().join(format!("say-bad-regex-{}.schema.json", std::process::id()));
+    std::fs::write(&schema, r#"{ "root": { "type": "String", "regex": "(" }, "validators": [] }"#).unwrap();
+    let bad_regex = say()
+        .arg("--schema")
+        .arg(&schema)
+        .arg(fixture("examples/conforming.json"))
+        .output()
+        .unwrap();
+    std::fs::remove_file(&schema).unwrap();
+    assert_eq!(Some(1), bad_regex.status.code());
+    assert!(String::from_utf8(bad_regex.stderr).unwrap().contains("invalid regex `(`"));
+}
+
+#[test]
+fn bitflag_exit_should_keep_usage_errors_out_of_the_flags() {
+    let output = say().arg("--bitflag-exit").arg(fixture("examples/conforming.json")).output().unwrap();
+    assert_eq!(Some(64), output.status.code());
+
+    let missing = say()
+        .arg("--bitflag-exit")
+        .arg("--schema")
+        .arg(fixture("user.schema.json"))
+        .arg(fixture("examples/missing.json"))
+        .output()
+        .unwrap();
+    assert_eq!(Some(64), missing.status.code());
+}
+
+#[test]
+fn say_should_ask_for_a_schema() {
+    let output = say().arg(fixture("examples/conforming.json")).output().unwrap();
+    assert_eq!(Some(2), output.status.code());
+    assert!(String::from_utf8(output.stderr).unwrap().contains("--schema"));
+}
+
+#[test]
+fn junit_format_should_report_a_testcase_per_file() {
+    let output = say()
+        .arg("--format")
+        .arg("junit")
+        .arg("--schema")
+        .arg(fixture("user.schema.json"))
+        .arg(fixture("examples/conforming.json"))
+        .arg(fixture("examples/broken.json"))
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<&str> = stdout.lines().collect();
+
+    assert_eq!(Some(1), output.status.code());
+    assert_eq!("<?xml version=\"1.0\" encoding=\"UTF-8\"?>", lines[0]);
+    assert!(lines[1].starts_with("<testsuite name=\"") && lines[1].ends_with("user.schema.json\" tests=\"2\" failures=\"1\">"));
+    assert!(lines[2].contains("conforming.json\" classname=\"say\"/>"));
+    assert!(lines[3].contains("broken.json\" classname=\"say\">"));
+    assert!(lines[4].starts_with("    <failure message=\"2 error(s)\">"));
+    assert!(stdout.contains("/email: expected"));
+    assert_eq!(Some(&"</testsuite>"), lines.last());
+}
+
+#[test]
+fn convention_should_pick_the_schema_by_file_name() {
+    let output = say()
+        .arg("--convention")
+        .arg("{name}.{kind}.json -> {kind}.schema.json")
+        .arg(fixture("convention/alice.user.json"))
+        .arg(fixture("convention/admins.group.json"))
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    assert_eq!(Some(1), output.status.code());
+    assert!(stdout.lines().any(|line| line.starts_with("valid") && line.ends_with("alice.user.json")));
+    assert!(stdout.lines().any(|line| line.starts_with("invalid") && line.ends_with("admins.group.json")));
+    assert!(stdout.contains("/members/1"));
+
+    let unmatched = say()
+        .arg("--convention")
+        .arg("{name}.{kind}.json -> {kind}.schema.json")
+        .arg(fixture("user.schema"))
+        .output()
+        .unwrap();
+    assert_eq!(Some(2), unmatched.status.code());
+    assert!(String::from_utf8(unmatched.stderr).unwrap().contains("does not match the convention"));
+}
+
+#[test]
+fn group_by_code_should_count_the_errors_of_each_code() {
+    let output = say()
+        .arg("--group-by-code")
+        .arg("--schema")
+        .arg(fixture("user.schema.json"))
+        .arg(fixture("mixed.json"))
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<&str> = stdout.lines().skip(1).collect();
+
+    assert_eq!(Some(1), output.status.code());
+    assert_eq!(
+        vec!["  unknown_field: 4 at /w, /x, /y and 1 more", "  missing_field: 1 at /admin", "  too_long: 1 at /name"],
+        lines
+    );
+}
+
+#[test]
+fn max_errors_should_cut_the_listing_short() {
+    let output = say()
+        .arg("--max-errors")
+        .arg("2")
+        .arg("--schema")
+        .arg(fixture("user.schema.json"))
+        .arg(fixture("mixed.json"))
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<&str> = stdout.lines().collect();
+
+    assert_eq!(Some(1), output.status.code());
+    assert_eq!(4, lines.len());
+    assert!(lines[0].starts_with("invalid"));
+    assert_eq!("  ... and 4 more", lines[3]);
+}
+
+#[test]
+fn content_type_should_select_the_schema() {
+    let by_content_type = |content_type: &str| {
+        say()
+            .arg("--content-type")
+            .arg(content_type)
+            .arg("--schema-for")
+            .arg(format!("application/problem+json={}", fixture("content-type/problem.schema.json").display()))
+            .arg("--schema")
+            .arg(fixture("user.schema.json"))
+            .arg(fixture("content-type/not-found.json"))
+            .output()
+            .unwrap()
+    };
+
+    assert_eq!(Some(0), by_content_type("application/problem+json; charset=utf-8").status.code());
+    let json = by_content_type("application/json");
+    assert_eq!(Some(1), json.status.code());
+    assert!(String::from_utf8(json.stdout).unwrap().contains("/name: expected required field"));
+}